@@ -16,12 +16,14 @@ use stm32f1xx_hal::{
 use usb_device::{bus, prelude::*};
 
 use usbd_hid::{
-    descriptor::{generator_prelude::*, MediaKey, MediaKeyboardReport},
+    descriptor::{generator_prelude::*, MediaKeyboardReport},
     hid_class::HIDClass,
 };
 
 use cortex_m::peripheral::DWT;
-use infrared::{hal::PeriodicReceiver, protocols::Nec, remotes::nec::SpecialForMp3, Button};
+use infrared::{
+    hal::PeriodicReceiver, hid::Usage, protocols::Nec, remotes::nec::SpecialForMp3, Button,
+};
 
 use rtic::cyccnt::{Instant, U32Ext};
 
@@ -148,7 +150,10 @@ const APP: () = {
             Ok(Some(button)) => {
                 rprintln!("Received: {:?}", button);
 
-                let key = button_to_mediakey(button);
+                let key = match button_to_mediakey(button) {
+                    Some(key) => key,
+                    None => return,
+                };
 
                 if let Err(err) = cx.spawn.keydown(key) {
                     rprintln!("Failed to spawn keydown: {:?}", err);
@@ -162,7 +167,7 @@ const APP: () = {
     }
 
     #[task(resources = [usb_kbd], schedule = [keyup])]
-    fn keydown(mut cx: keydown::Context, key: MediaKey) {
+    fn keydown(mut cx: keydown::Context, key: u16) {
         rprintln!("keydown  @ {:?}", Instant::now());
         cx.resources.usb_kbd.lock(|kbd| send_keycode(kbd, key));
 
@@ -176,7 +181,7 @@ const APP: () = {
         rprintln!("keyup  @ {:?}", Instant::now());
         cx.resources
             .usb_kbd
-            .lock(|kbd| send_keycode(kbd, MediaKey::Zero));
+            .lock(|kbd| send_keycode(kbd, 0));
     }
 
     extern "C" {
@@ -191,10 +196,8 @@ fn usb_poll<B: bus::UsbBus>(
     while usb_dev.poll(&mut [usb_kbd]) {}
 }
 
-fn send_keycode(kbd: &HIDClass<UsbBusType>, key: MediaKey) {
-    let report = MediaKeyboardReport {
-        usage_id: key.into(),
-    };
+fn send_keycode(kbd: &HIDClass<UsbBusType>, key: u16) {
+    let report = MediaKeyboardReport { usage_id: key };
 
     loop {
         let r = kbd.push_input(&report);
@@ -208,14 +211,10 @@ fn send_keycode(kbd: &HIDClass<UsbBusType>, key: MediaKey) {
     }
 }
 
-fn button_to_mediakey(b: Button) -> MediaKey {
-    match b {
-        Button::Play_Paus => MediaKey::PlayPause,
-        Button::Plus => MediaKey::VolumeIncrement,
-        Button::Minus => MediaKey::VolumeDecrement,
-        Button::Next => MediaKey::NextTrack,
-        Button::Prev => MediaKey::PrevTrack,
-        Button::Stop => MediaKey::Stop,
-        _ => MediaKey::Zero,
+/// The media keyboard only sends consumer page usages
+fn button_to_mediakey(b: Button) -> Option<u16> {
+    match b.hid_usage() {
+        Some(Usage::Consumer(id)) => Some(id),
+        _ => None,
     }
 }
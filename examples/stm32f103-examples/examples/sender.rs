@@ -71,18 +71,20 @@ fn main() -> ! {
     let pwm = Timer::tim4(device.TIM4, &clocks, &mut rcc.apb1).pwm::<Tim4NoRemap, _, _, _>(
         irled,
         &mut afio.mapr,
-        38.khz(),
+        Rc5Sender::CARRIER.frequency.hz(),
     );
 
     let mut irpin = pwm.split();
 
-    irpin.set_duty(irpin.get_max_duty() / 2);
+    let transmitter = Rc5Sender::new(TIMER_FREQ);
+
+    transmitter.setup_pwm(&mut irpin);
     irpin.disable();
 
     // Safe because the devices are only used in the interrupt handler
     unsafe {
         TIMER.replace(timer);
-        TRANSMITTER.replace(Rc5Sender::new(TIMER_FREQ));
+        TRANSMITTER.replace(transmitter);
         PWMCHANNEL.replace(irpin);
     }
 
@@ -0,0 +1,488 @@
+//! Analyzer for frames of unknown protocols
+//!
+//! Takes a frame of mark and space durations in micro seconds, as recorded by `RawCapture`, and
+//! guesses how it is encoded. The result is a set of `Params` that can decode and encode other
+//! frames of the same protocol, and the bits of the frame.
+
+use core::fmt;
+use std::vec::Vec;
+
+/// Durations within this factor of the shortest duration of a cluster are in the cluster
+const CLUSTER_SPREAD: (u32, u32) = (3, 2);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// How the bits are encoded
+pub enum Coding {
+    /// Fixed marks, the length of the space tells the bit (Nec, Sbp)
+    PulseDistance,
+    /// Fixed spaces, the length of the mark tells the bit (Sony)
+    PulseWidth,
+    /// Each bit has a transition in the middle (Rc5, Rc6)
+    Biphase,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Order of the bits in a field
+pub enum BitOrder {
+    /// Least significant bit sent first
+    LsbFirst,
+    /// Most significant bit sent first
+    MsbFirst,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Durations of similar length
+pub struct Cluster {
+    /// Mean duration in micro seconds
+    pub center: u32,
+    /// Number of durations in the cluster
+    pub count: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Parameters of a protocol, all durations in micro seconds
+pub struct Params {
+    pub coding: Coding,
+    /// Shortest duration used for the bits
+    pub unit: u32,
+    /// Leading mark and space
+    pub header: Option<(u32, u32)>,
+    /// Mark and space of a zero. For biphase, the half bits of a zero, a mark followed by a space.
+    pub zero: (u32, u32),
+    /// Mark and space of a one. For biphase, the half bits of a one, a space followed by a mark.
+    pub one: (u32, u32),
+    /// Mark ending the frame
+    pub trailer: Option<u32>,
+    /// Number of bits in a frame
+    pub bits: usize,
+}
+
+impl Params {
+    /// Decode the bits of `pulses`, a frame starting and ending with a mark
+    ///
+    /// Returns None if the frame doesn't match the parameters.
+    pub fn decode(&self, pulses: &[u32]) -> Option<Vec<bool>> {
+        let bits = self.decode_bits(pulses)?;
+        (bits.len() == self.bits).then_some(bits)
+    }
+
+    /// Encode `bits` into a frame of mark and space durations, starting and ending with a mark
+    pub fn encode(&self, bits: &[bool]) -> Vec<u32> {
+        let mut pulses = Vec::new();
+
+        if let Some((mark, space)) = self.header {
+            pulses.extend([mark, space]);
+        }
+
+        for &bit in bits {
+            let (first, second) = if bit { self.one } else { self.zero };
+
+            match self.coding {
+                Coding::PulseDistance | Coding::PulseWidth => pulses.extend([first, second]),
+                Coding::Biphase => {
+                    push_level(&mut pulses, !bit, first);
+                    push_level(&mut pulses, bit, second);
+                }
+            }
+        }
+
+        if let Some(trailer) = self.trailer {
+            push_level(&mut pulses, true, trailer);
+        }
+
+        // No trailing space
+        if pulses.len() % 2 == 0 {
+            pulses.pop();
+        }
+
+        pulses
+    }
+
+    fn decode_bits(&self, pulses: &[u32]) -> Option<Vec<bool>> {
+        let data = match self.header {
+            Some((mark, space)) => {
+                if pulses.len() < 2 || !close(pulses[0], mark) || !close(pulses[1], space) {
+                    return None;
+                }
+                &pulses[2..]
+            }
+            None => pulses,
+        };
+
+        let data = match self.trailer {
+            Some(trailer) => {
+                let (last, data) = data.split_last()?;
+                if !close(*last, trailer) {
+                    return None;
+                }
+                data
+            }
+            None => data,
+        };
+
+        match self.coding {
+            Coding::PulseDistance => {
+                let limit = self.zero.1 / 2 + self.one.1 / 2;
+                data.chunks(2)
+                    .map(|pair| pair.get(1).map(|&space| space > limit))
+                    .collect()
+            }
+            Coding::PulseWidth => {
+                let limit = self.zero.0 / 2 + self.one.0 / 2;
+                Some(data.iter().step_by(2).map(|&mark| mark > limit).collect())
+            }
+            Coding::Biphase => biphase(data, self.unit, self.header.is_none()),
+        }
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let coding = match self.coding {
+            Coding::PulseDistance => "pulse distance",
+            Coding::PulseWidth => "pulse width",
+            Coding::Biphase => "biphase",
+        };
+        write!(f, "{}, unit {} us", coding, self.unit)?;
+
+        if let Some((mark, space)) = self.header {
+            write!(f, ", header +{} -{}", mark, space)?;
+        }
+        write!(f, ", zero +{} -{}", self.zero.0, self.zero.1)?;
+        write!(f, ", one +{} -{}", self.one.0, self.one.1)?;
+        if let Some(trailer) = self.trailer {
+            write!(f, ", trailer +{}", trailer)?;
+        }
+        write!(f, ", {} bits", self.bits)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Result of analyzing a frame
+pub struct Analysis {
+    /// Clusters of the mark durations, shortest first
+    pub marks: Vec<Cluster>,
+    /// Clusters of the space durations, shortest first
+    pub spaces: Vec<Cluster>,
+    /// Parameters of the protocol
+    pub params: Params,
+    /// The bits of the frame, in the order they were sent
+    pub bits: Vec<bool>,
+}
+
+impl Analysis {
+    /// Guess of the bit order
+    ///
+    /// The frame doesn't tell, so this goes by what is common for the coding. Pulse distance
+    /// and pulse width protocols are usually sent least significant bit first, biphase
+    /// protocols most significant bit first.
+    pub fn bit_order(&self) -> BitOrder {
+        match self.params.coding {
+            Coding::PulseDistance | Coding::PulseWidth => BitOrder::LsbFirst,
+            Coding::Biphase => BitOrder::MsbFirst,
+        }
+    }
+
+    /// The first 64 bits as a number
+    pub fn value(&self, order: BitOrder) -> u64 {
+        let bits = self.bits.iter().take(64);
+
+        match order {
+            BitOrder::LsbFirst => bits
+                .enumerate()
+                .fold(0, |value, (i, &bit)| value | (u64::from(bit) << i)),
+            BitOrder::MsbFirst => bits.fold(0, |value, &bit| (value << 1) | u64::from(bit)),
+        }
+    }
+
+    /// The bits split into bytes. The last byte is padded with zeros if the number of bits
+    /// isn't a multiple of eight.
+    pub fn bytes(&self, order: BitOrder) -> Vec<u8> {
+        self.bits
+            .chunks(8)
+            .map(|chunk| {
+                chunk.iter().enumerate().fold(0, |byte, (i, &bit)| {
+                    let shift = match order {
+                        BitOrder::LsbFirst => i,
+                        BitOrder::MsbFirst => 7 - i,
+                    };
+                    byte | (u8::from(bit) << shift)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Analyze a frame of mark and space durations in micro seconds, starting and ending with a mark
+///
+/// The frame should hold a single transmission, repeats are split off by `RawCapture`.
+/// Returns None if the coding couldn't be determined. Biphase protocols with bits of double
+/// length, like the trailer bit of Rc6, are not recognized.
+pub fn analyze(pulses: &[u32]) -> Option<Analysis> {
+    if pulses.len() < 3 {
+        return None;
+    }
+
+    let marks = cluster(pulses.iter().step_by(2));
+    let spaces = cluster(pulses.iter().skip(1).step_by(2));
+
+    // A leading mark well over the longest of the other marks is a header. The Sony header is
+    // only twice the mark of a one, so allow for some jitter.
+    let longest = cluster(pulses.iter().skip(2).step_by(2)).last()?.center;
+    let header =
+        (u64::from(pulses[0]) * 2 > u64::from(longest) * 3).then(|| (pulses[0], pulses[1]));
+    let data = if header.is_some() {
+        &pulses[2..]
+    } else {
+        pulses
+    };
+
+    let data_marks = cluster(data.iter().step_by(2));
+    let data_spaces = cluster(data.iter().skip(1).step_by(2));
+
+    let mut params = match (data_marks.as_slice(), data_spaces.as_slice()) {
+        ([short_mark, long_mark], [short_space, long_space])
+            if double(short_mark, long_mark) && double(short_space, long_space) =>
+        {
+            let unit = short_mark.center.min(short_space.center);
+            Params {
+                coding: Coding::Biphase,
+                unit,
+                header,
+                zero: (unit, unit),
+                one: (unit, unit),
+                trailer: None,
+                bits: 0,
+            }
+        }
+        ([mark], [zero, one]) => Params {
+            coding: Coding::PulseDistance,
+            unit: mark.center.min(zero.center),
+            header,
+            zero: (mark.center, zero.center),
+            one: (mark.center, one.center),
+            // Every bit is a mark and a space, the last mark ends the frame
+            trailer: Some(mark.center),
+            bits: 0,
+        },
+        ([zero, one], [space]) => Params {
+            coding: Coding::PulseWidth,
+            unit: zero.center.min(space.center),
+            header,
+            zero: (zero.center, space.center),
+            one: (one.center, space.center),
+            trailer: None,
+            bits: 0,
+        },
+        _ => return None,
+    };
+
+    let bits = params.decode_bits(pulses)?;
+    params.bits = bits.len();
+
+    Some(Analysis {
+        marks,
+        spaces,
+        params,
+        bits,
+    })
+}
+
+/// Group the durations into clusters of similar length
+fn cluster<'a>(durations: impl Iterator<Item = &'a u32>) -> Vec<Cluster> {
+    let mut sorted: Vec<u32> = durations.copied().collect();
+    sorted.sort_unstable();
+
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let (mut first, mut sum) = (0, 0u64);
+
+    for &d in &sorted {
+        match clusters.last_mut() {
+            Some(last)
+                if u64::from(d) * u64::from(CLUSTER_SPREAD.1)
+                    <= u64::from(first) * u64::from(CLUSTER_SPREAD.0) =>
+            {
+                sum += u64::from(d);
+                last.count += 1;
+                last.center = (sum / last.count as u64) as u32;
+            }
+            _ => {
+                first = d;
+                sum = u64::from(d);
+                clusters.push(Cluster {
+                    center: d,
+                    count: 1,
+                });
+            }
+        }
+    }
+
+    clusters
+}
+
+/// Is the long cluster about twice the short one
+fn double(short: &Cluster, long: &Cluster) -> bool {
+    let short = u64::from(short.center);
+    (short * 3 / 2..=short * 5 / 2).contains(&u64::from(long.center))
+}
+
+/// Is `duration` within 25% of `expected`
+fn close(duration: u32, expected: u32) -> bool {
+    u64::from(duration.abs_diff(expected)) * 4 <= u64::from(expected)
+}
+
+/// Decode biphase coded durations, a one is a space followed by a mark
+///
+/// The first bit may start with a space, which can't be seen before the first mark. Both
+/// alignments are tried, starting with the one given by `leading_space`.
+fn biphase(data: &[u32], unit: u32, leading_space: bool) -> Option<Vec<bool>> {
+    // Half bits, true for a mark
+    let mut halves = Vec::new();
+    for (i, &d) in data.iter().enumerate() {
+        let n = d.saturating_add(unit / 2) / unit;
+        if !(1..=2).contains(&n) {
+            return None;
+        }
+        halves.extend(core::iter::repeat(i % 2 == 0).take(n as usize));
+    }
+
+    let align = |lead: bool| -> Option<Vec<bool>> {
+        let mut h = Vec::with_capacity(halves.len() + 2);
+        if lead {
+            h.push(false);
+        }
+        h.extend(&halves);
+        // The trailing space after the last bit can't be seen either
+        if h.len() % 2 == 1 {
+            h.push(false);
+        }
+        h.chunks(2)
+            .map(|pair| (pair[0] != pair[1]).then_some(pair[1]))
+            .collect()
+    };
+
+    align(leading_space).or_else(|| align(!leading_space))
+}
+
+/// Add a duration at `mark` level, merging it with the previous one at the same level
+fn push_level(pulses: &mut Vec<u32>, mark: bool, duration: u32) {
+    let last_is_mark = pulses.len() % 2 == 1;
+
+    match pulses.last_mut() {
+        // Leading space
+        None if !mark => {}
+        Some(last) if mark == last_is_mark => *last += duration,
+        _ => pulses.push(duration),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::analyze;
+
+    #[test]
+    fn long_durations() {
+        for durations in [
+            &[u32::MAX, 1, 1][..],
+            &[1, u32::MAX, 1],
+            &[u32::MAX, u32::MAX / 2, u32::MAX, u32::MAX / 2, u32::MAX],
+            &[u32::MAX; 9],
+            &[0; 9],
+        ] {
+            let _ = analyze(durations);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "nec")]
+    fn pulse_distance() {
+        use super::{BitOrder, Coding};
+        use crate::protocols::nec;
+
+        let pulses = nec::tests::frame();
+
+        let analysis = analyze(&pulses).unwrap();
+        let params = analysis.params;
+
+        assert_eq!(params.coding, Coding::PulseDistance);
+        assert_eq!(params.unit, 560);
+        assert_eq!(params.header, Some((9000, 4500)));
+        assert_eq!(params.trailer, Some(560));
+        assert_eq!(params.bits, 32);
+        assert_eq!(analysis.bit_order(), BitOrder::LsbFirst);
+        assert_eq!(analysis.bytes(BitOrder::LsbFirst), [7, !7, 44, !44]);
+
+        // The parameters decode and encode the frame
+        assert_eq!(params.decode(&pulses), Some(analysis.bits.clone()));
+        assert_eq!(params.encode(&analysis.bits), pulses);
+    }
+
+    #[test]
+    #[cfg(feature = "rc5")]
+    fn biphase() {
+        use super::{BitOrder, Coding};
+        use crate::protocols::rc5::{Rc5Command, Rc5Sender};
+        use crate::sender::PulseSender;
+
+        let mut pulses = [0u32; 28];
+        let n = Rc5Sender::pulses(&Rc5Command::new(20, 53, false), &mut pulses).unwrap();
+
+        let analysis = analyze(&pulses[..n]).unwrap();
+        let params = analysis.params;
+
+        assert_eq!(params.coding, Coding::Biphase);
+        assert_eq!(params.unit, 889);
+        assert_eq!(params.header, None);
+        assert_eq!(params.bits, 14);
+        assert_eq!(analysis.bit_order(), BitOrder::MsbFirst);
+        // Start bits, toggle, address and command
+        let value = analysis.value(BitOrder::MsbFirst);
+        assert_eq!(
+            (value >> 12, (value >> 6) & 0x3F, value & 0x3F),
+            (0b11, 20, 53)
+        );
+        assert_eq!(params.encode(&analysis.bits), &pulses[..n]);
+    }
+
+    /// Sony SIRC frame of 12 bits, sent lsb first. `jitter` is added to the marks and taken
+    /// from the spaces.
+    fn sirc(value: u16, jitter: u32) -> Vec<u32> {
+        let mut pulses = vec![2400 + jitter, 600 - jitter];
+        for bit in 0..12 {
+            let mark = if value & (1 << bit) != 0 { 1200 } else { 600 };
+            pulses.extend([mark + jitter, 600 - jitter]);
+        }
+        pulses.pop();
+        pulses
+    }
+
+    #[test]
+    fn pulse_width() {
+        use super::{BitOrder, Coding};
+
+        // Command 21 to address 1
+        let value = 21 | (1 << 7);
+
+        let pulses = sirc(value, 0);
+        let analysis = analyze(&pulses).unwrap();
+        let params = analysis.params;
+
+        assert_eq!(params.coding, Coding::PulseWidth);
+        assert_eq!(params.unit, 600);
+        assert_eq!(params.header, Some((2400, 600)));
+        assert_eq!(params.zero, (600, 600));
+        assert_eq!(params.one, (1200, 600));
+        assert_eq!(params.trailer, None);
+        assert_eq!(params.bits, 12);
+        assert_eq!(analysis.bit_order(), BitOrder::LsbFirst);
+        assert_eq!(analysis.value(BitOrder::LsbFirst), u64::from(value));
+
+        assert_eq!(params.decode(&pulses), Some(analysis.bits.clone()));
+        assert_eq!(params.encode(&analysis.bits), pulses);
+
+        // Marks stretched by the receiver, the header is less than twice the mark of a one
+        let analysis = analyze(&sirc(value, 50)).unwrap();
+        assert_eq!(analysis.params.header, Some((2450, 550)));
+        assert_eq!(analysis.value(BitOrder::LsbFirst), u64::from(value));
+    }
+}
@@ -0,0 +1,391 @@
+//! Button press, hold and release detection
+
+use crate::{Button, Command, Protocol};
+
+/// Default time without frames before a held button is considered released
+const RELEASE_MS: u32 = 200;
+/// Default time a button must be held to be a long press
+const LONGPRESS_MS: u32 = 1000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Button event
+pub enum ButtonEvent {
+    /// Button was pressed
+    Pressed(Button),
+    /// Button is still held, one event for each repeated frame
+    Repeated(Button),
+    /// Button has been held for the long press time
+    LongPress(Button),
+    /// Button was released
+    Released(Button),
+}
+
+/// The events from one call to `ButtonTracker::command`, in the order they happened
+#[derive(Debug, Clone, Default)]
+pub struct ButtonEvents {
+    events: [Option<ButtonEvent>; 2],
+    next: usize,
+}
+
+impl ButtonEvents {
+    /// No events left
+    pub fn is_empty(&self) -> bool {
+        self.events[self.next.min(2)..].iter().all(Option::is_none)
+    }
+
+    fn push(&mut self, event: ButtonEvent) {
+        if let Some(slot) = self.events.iter_mut().find(|e| e.is_none()) {
+            *slot = Some(event);
+        }
+    }
+}
+
+impl From<Option<ButtonEvent>> for ButtonEvents {
+    fn from(event: Option<ButtonEvent>) -> Self {
+        Self {
+            events: [event, None],
+            next: 0,
+        }
+    }
+}
+
+impl Iterator for ButtonEvents {
+    type Item = ButtonEvent;
+
+    fn next(&mut self) -> Option<ButtonEvent> {
+        let event = self.events.get_mut(self.next)?.take();
+        self.next += 1;
+        event
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Held {
+    button: Button,
+    /// Toggle bit of the press
+    toggle: Option<bool>,
+    /// Timestamp of the press
+    pressed: u32,
+    /// Timestamp of the last frame
+    last: u32,
+    /// LongPress already emitted
+    long: bool,
+}
+
+/// Turns decoded commands into button events
+///
+/// A new press is told apart from a held button by the toggle bit for Rc5 and Rc6, and for Nec
+/// by the repeat frames sent while a button is held: a full frame is always a new press. For
+/// protocols without either, such as Nec Samsung that repeats the full frame, the same button
+/// received again before the release timeout is a repeat.
+///
+/// All timestamps are in samples at the samplerate given to `new`, and are allowed to wrap.
+pub struct ButtonTracker {
+    held: Option<Held>,
+    /// Release timeout in samples
+    release: u32,
+    /// Long press time in samples
+    longpress: u32,
+}
+
+impl ButtonTracker {
+    /// Create a new tracker with the default timeouts
+    pub fn new(samplerate: u32) -> Self {
+        Self::with_timeouts(samplerate, RELEASE_MS, LONGPRESS_MS)
+    }
+
+    /// Create a new tracker
+    /// `release_ms`: Time without frames before the button is released. Should be longer than
+    /// the repeat period of the protocol.
+    /// `longpress_ms`: Time the button must be held to generate a `LongPress` event
+    pub fn with_timeouts(samplerate: u32, release_ms: u32, longpress_ms: u32) -> Self {
+        Self {
+            held: None,
+            release: ms_to_samples(samplerate, release_ms),
+            longpress: ms_to_samples(samplerate, longpress_ms),
+        }
+    }
+
+    /// Add a received command, decoded into `button`, at timestamp `ts`
+    ///
+    /// Returns up to two events, for example the release of the held button followed by the
+    /// press of the new one.
+    pub fn command<C: Command + ?Sized>(
+        &mut self,
+        cmd: &C,
+        button: Button,
+        ts: u32,
+    ) -> ButtonEvents {
+        let mut events = ButtonEvents::default();
+        let toggle = cmd.toggle();
+
+        // Released without a poll since the last frame
+        if let Some(held) = self.held {
+            if ts.wrapping_sub(held.last) >= self.release {
+                self.held = None;
+                events.push(ButtonEvent::Released(held.button));
+            }
+        }
+
+        if let Some(held) = &mut self.held {
+            let same_press = if cmd.is_repeat() {
+                true
+            } else if toggle.is_some() {
+                held.button == button && held.toggle == toggle
+            } else if has_repeat_frames(cmd.protocol()) {
+                false
+            } else {
+                held.button == button
+            };
+
+            if same_press {
+                held.last = ts;
+                events.push(
+                    if !held.long && ts.wrapping_sub(held.pressed) >= self.longpress {
+                        held.long = true;
+                        ButtonEvent::LongPress(held.button)
+                    } else {
+                        ButtonEvent::Repeated(held.button)
+                    },
+                );
+                return events;
+            }
+        } else if cmd.is_repeat() {
+            // Repeat of a press we didn't see
+            return events;
+        }
+
+        if let Some(held) = self.held.take() {
+            events.push(ButtonEvent::Released(held.button));
+        }
+
+        self.held = Some(Held {
+            button,
+            toggle,
+            pressed: ts,
+            last: ts,
+            long: false,
+        });
+        events.push(ButtonEvent::Pressed(button));
+        events
+    }
+
+    /// Check the timeouts. Should be called periodically, also when no commands are received.
+    pub fn poll(&mut self, ts: u32) -> Option<ButtonEvent> {
+        let held = self.held.as_mut()?;
+
+        if ts.wrapping_sub(held.last) >= self.release {
+            let button = held.button;
+            self.held = None;
+            return Some(ButtonEvent::Released(button));
+        }
+
+        if !held.long && ts.wrapping_sub(held.pressed) >= self.longpress {
+            held.long = true;
+            return Some(ButtonEvent::LongPress(held.button));
+        }
+
+        None
+    }
+
+    /// The currently held button
+    pub fn held(&self) -> Option<Button> {
+        self.held.map(|held| held.button)
+    }
+
+    /// Forget the held button
+    pub fn reset(&mut self) {
+        self.held = None;
+    }
+}
+
+/// Does the protocol send repeat frames, rather than the full frame, while a button is held
+fn has_repeat_frames(protocol: Protocol) -> bool {
+    matches!(protocol, Protocol::Nec | Protocol::Nec16)
+}
+
+fn ms_to_samples(samplerate: u32, ms: u32) -> u32 {
+    (u64::from(samplerate) * u64::from(ms) / 1000) as u32
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "nec", feature = "rc5"))]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{ButtonEvent, ButtonEvent::*, ButtonTracker};
+    use crate::protocols::{
+        nec::{NecCommand, NecSamsung},
+        rc5::Rc5Command,
+    };
+    use crate::{Button, Command};
+
+    fn command(
+        tracker: &mut ButtonTracker,
+        cmd: &dyn Command,
+        button: Button,
+        ts: u32,
+    ) -> Vec<ButtonEvent> {
+        tracker.command(cmd, button, ts).collect()
+    }
+
+    #[test]
+    fn toggle() {
+        // 1 kHz, timestamps in ms
+        let mut tracker = ButtonTracker::new(1_000);
+
+        let play = Rc5Command::new(20, 53, false);
+        assert_eq!(
+            command(&mut tracker, &play, Button::Play, 0),
+            [Pressed(Button::Play)]
+        );
+        assert_eq!(
+            command(&mut tracker, &play, Button::Play, 114),
+            [Repeated(Button::Play)]
+        );
+        assert_eq!(tracker.poll(200), None);
+
+        // New press of the same button, the toggle bit flipped
+        let play = Rc5Command::new(20, 53, true);
+        assert_eq!(
+            command(&mut tracker, &play, Button::Play, 228),
+            [Released(Button::Play), Pressed(Button::Play)]
+        );
+
+        for ts in (342..1200).step_by(114) {
+            assert_eq!(
+                command(&mut tracker, &play, Button::Play, ts),
+                [Repeated(Button::Play)]
+            );
+        }
+        assert_eq!(
+            command(&mut tracker, &play, Button::Play, 1254),
+            [LongPress(Button::Play)]
+        );
+        assert_eq!(tracker.poll(1300), None);
+        assert_eq!(
+            command(&mut tracker, &play, Button::Play, 1368),
+            [Repeated(Button::Play)]
+        );
+        assert_eq!(tracker.poll(1567), None);
+        assert_eq!(tracker.poll(1568), Some(Released(Button::Play)));
+        assert_eq!(tracker.held(), None);
+    }
+
+    #[test]
+    fn repeat() {
+        let mut tracker = ButtonTracker::with_timeouts(1_000, 150, 300);
+
+        let power: NecCommand = NecCommand::new(7, 2);
+        let mut repeat = power;
+        repeat.repeat = true;
+
+        // Repeat without a press is ignored
+        assert_eq!(command(&mut tracker, &repeat, Button::Power, 0), []);
+
+        assert_eq!(
+            command(&mut tracker, &power, Button::Power, 100),
+            [Pressed(Button::Power)]
+        );
+        assert_eq!(
+            command(&mut tracker, &repeat, Button::Power, 208),
+            [Repeated(Button::Power)]
+        );
+        assert_eq!(
+            command(&mut tracker, &repeat, Button::Power, 316),
+            [Repeated(Button::Power)]
+        );
+        assert_eq!(
+            command(&mut tracker, &repeat, Button::Power, 424),
+            [LongPress(Button::Power)]
+        );
+        assert_eq!(tracker.held(), Some(Button::Power));
+
+        // A full frame is a new press, also of the held button
+        assert_eq!(
+            command(&mut tracker, &power, Button::Power, 532),
+            [Released(Button::Power), Pressed(Button::Power)]
+        );
+        assert_eq!(tracker.poll(682), Some(Released(Button::Power)));
+    }
+
+    #[test]
+    fn full_frame_repeats() {
+        let mut tracker = ButtonTracker::with_timeouts(1_000, 150, 300);
+
+        // Nec Samsung repeats the full frame
+        let power: NecCommand<NecSamsung> = NecCommand::new(7, 2);
+
+        assert_eq!(
+            command(&mut tracker, &power, Button::Power, 0),
+            [Pressed(Button::Power)]
+        );
+        assert_eq!(
+            command(&mut tracker, &power, Button::Power, 108),
+            [Repeated(Button::Power)]
+        );
+        assert_eq!(
+            command(&mut tracker, &power, Button::Power, 324),
+            [Released(Button::Power), Pressed(Button::Power)]
+        );
+    }
+
+    #[test]
+    fn release_without_poll() {
+        let mut tracker = ButtonTracker::with_timeouts(1_000, 150, 300);
+
+        let play = Rc5Command::new(20, 53, false);
+        assert_eq!(
+            command(&mut tracker, &play, Button::Play, 0),
+            [Pressed(Button::Play)]
+        );
+
+        // Same toggle bit, but long after the release timeout
+        assert_eq!(
+            command(&mut tracker, &play, Button::Play, 10_000),
+            [Released(Button::Play), Pressed(Button::Play)]
+        );
+
+        // A repeat after the release timeout only releases the button
+        let power: NecCommand = NecCommand::new(7, 2);
+        let mut repeat = power;
+        repeat.repeat = true;
+        assert_eq!(
+            command(&mut tracker, &power, Button::Power, 10_100),
+            [Released(Button::Play), Pressed(Button::Power)]
+        );
+        assert_eq!(
+            command(&mut tracker, &repeat, Button::Power, 10_300),
+            [Released(Button::Power)]
+        );
+        assert_eq!(tracker.held(), None);
+    }
+
+    #[test]
+    fn presses_without_poll() {
+        let mut tracker = ButtonTracker::new(1_000);
+
+        let one: NecCommand = NecCommand::new(7, 1);
+        let two: NecCommand = NecCommand::new(7, 2);
+
+        let mut events = Vec::new();
+        for (cmd, button, ts) in [
+            (one, Button::One, 0),
+            (two, Button::Two, 50),
+            (one, Button::One, 100),
+        ] {
+            events.extend(tracker.command(&cmd, button, ts));
+        }
+
+        assert_eq!(
+            events,
+            [
+                Pressed(Button::One),
+                Released(Button::One),
+                Pressed(Button::Two),
+                Released(Button::Two),
+                Pressed(Button::One),
+            ]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Decoding of timer input capture buffers
+
+use crate::recv::{EventReceiver, ReceiverSM};
+
+/// Streaming decoder for edges captured by a hardware timer, for example with DMA
+///
+/// The edges are given as absolute counter values, in chunks as they are read out of the
+/// capture buffer. The state is kept between chunks, so a frame can be split over any number of
+/// them. A counter narrower than 32 bits is handled with `with_counter_bits`.
+///
+/// The polarity of an edge is true for the start of a mark, and false for the end of it. When
+/// two edges with the same polarity are seen in a row, an edge was missed and the decoder is
+/// resynced. A frame broken by noise is dropped, and decoding continues from the edge that broke
+/// it.
+pub struct CaptureReceiver<SM: ReceiverSM> {
+    recv: EventReceiver<SM>,
+    /// Timestamp and polarity of the last edge
+    last: Option<(u32, bool)>,
+    /// Mask for the valid counter bits
+    mask: u32,
+    /// Time since the last edge, in ticks, used for the first edge and after a resync
+    idle: u32,
+    /// Command waiting to be returned
+    pending: Option<SM::Cmd>,
+}
+
+impl<SM: ReceiverSM> CaptureReceiver<SM> {
+    /// Create a new decoder for a 32 bit counter running at `tickrate`
+    pub fn new(tickrate: u32) -> Self {
+        Self::with_counter_bits(tickrate, 32)
+    }
+
+    /// Create a new decoder for a `bits` wide counter running at `tickrate`
+    pub fn with_counter_bits(tickrate: u32, bits: u32) -> Self {
+        let mask = u32::MAX >> (32 - bits.clamp(1, 32));
+
+        Self {
+            recv: EventReceiver::new(tickrate),
+            last: None,
+            mask,
+            // A second, or as long as the counter can measure
+            idle: mask.min(tickrate),
+            pending: None,
+        }
+    }
+
+    /// Decode a chunk of `(timestamp, polarity)` edges
+    pub fn decode<'a, I>(&'a mut self, edges: I) -> impl Iterator<Item = SM::Cmd> + 'a
+    where
+        I: IntoIterator<Item = (u32, bool)>,
+        I::IntoIter: 'a,
+    {
+        let mut edges = edges.into_iter();
+
+        core::iter::from_fn(move || loop {
+            if let Some(cmd) = self.pending.take() {
+                return Some(cmd);
+            }
+
+            let (ts, edge) = edges.next()?;
+            if let Some(cmd) = self.edge(ts, edge) {
+                return Some(cmd);
+            }
+        })
+    }
+
+    /// Decode a chunk of timestamps from a capture on both edges, with alternating polarity
+    ///
+    /// The polarity follows from the last edge of the previous chunk. The first edge ever is
+    /// taken to be the start of a mark.
+    pub fn decode_alternating<'a, T>(
+        &'a mut self,
+        timestamps: &'a [T],
+    ) -> impl Iterator<Item = SM::Cmd> + 'a
+    where
+        T: Into<u32> + Copy,
+    {
+        let mut edge = self.last.map_or(true, |(_, edge)| !edge);
+
+        self.decode(timestamps.iter().map(move |ts| {
+            let e = edge;
+            edge = !edge;
+            ((*ts).into(), e)
+        }))
+    }
+
+    /// Reset the decoder
+    pub fn reset(&mut self) {
+        self.recv.reset();
+        self.last = None;
+        self.pending = None;
+    }
+
+    fn edge(&mut self, ts: u32, edge: bool) -> Option<SM::Cmd> {
+        let dt = match self.last.replace((ts, edge)) {
+            Some((last, last_edge)) if last_edge != edge => ts.wrapping_sub(last) & self.mask,
+            Some(_) => {
+                // Missed an edge, start over from this one
+                self.recv.reset();
+                self.idle
+            }
+            None => self.idle,
+        };
+
+        let timed_out = self.recv.timeout_check(dt);
+
+        let cmd = match self.recv.edge_event(edge, dt) {
+            Ok(cmd) => cmd,
+            // The edge that broke the frame could be the start of the next one
+            Err(_) => self.recv.edge_event(edge, dt).ok().flatten(),
+        };
+
+        match timed_out {
+            Some(timed_out) => {
+                self.pending = cmd;
+                Some(timed_out)
+            }
+            None => cmd,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "nec")]
+mod tests {
+    use std::vec::Vec;
+
+    use super::CaptureReceiver;
+    use crate::protocols::{nec, Nec};
+
+    #[test]
+    fn chunks() {
+        let pulses = nec::tests::frame();
+
+        // 16 bit counter at 1 MHz, starting close to the wrap
+        let mut ts: u32 = 60_000;
+        let mut timestamps: Vec<u16> = Vec::new();
+        let frame = |ts: &mut u32, timestamps: &mut Vec<u16>, glitch: Option<usize>| {
+            for (i, dt) in pulses.iter().enumerate() {
+                timestamps.push(*ts as u16);
+                if Some(i) == glitch {
+                    // Short spike in a mark
+                    timestamps.push((*ts + 100) as u16);
+                    timestamps.push((*ts + 120) as u16);
+                }
+                *ts += dt;
+            }
+            timestamps.push(*ts as u16);
+            *ts += 40_000;
+        };
+
+        frame(&mut ts, &mut timestamps, None);
+        frame(&mut ts, &mut timestamps, Some(20));
+        frame(&mut ts, &mut timestamps, None);
+
+        let mut recv: CaptureReceiver<Nec> = CaptureReceiver::with_counter_bits(1_000_000, 16);
+        let mut cmds = Vec::new();
+        for chunk in timestamps.chunks(7) {
+            cmds.extend(recv.decode_alternating(chunk));
+        }
+
+        assert_eq!(cmds.len(), 2);
+        assert!(cmds.iter().all(|cmd| (cmd.addr, cmd.cmd) == (7, 44)));
+    }
+
+    #[test]
+    fn missed_edge() {
+        let pulses = nec::tests::frame();
+
+        let mut edges = Vec::new();
+        let mut ts = 0;
+        for frame in 0..2 {
+            for (i, dt) in pulses.iter().enumerate() {
+                edges.push((ts, i & 0x1 == 0));
+                ts += dt;
+            }
+            edges.push((ts, false));
+            ts += 40_000;
+
+            if frame == 0 {
+                // Lose an edge in the first frame
+                edges.remove(30);
+            }
+        }
+
+        let mut recv: CaptureReceiver<Nec> = CaptureReceiver::new(1_000_000);
+        let cmds: Vec<_> = recv.decode(edges).collect();
+
+        assert_eq!(cmds.len(), 1);
+        assert_eq!((cmds[0].addr, cmds[0].cmd), (7, 44));
+    }
+}
@@ -15,9 +15,22 @@ pub trait Command {
     fn protocol(&self) -> Protocol {
         Protocol::Unknown
     }
+
+    /// Is this a repeat of the previous command, such as a Nec repeat frame
+    fn is_repeat(&self) -> bool {
+        false
+    }
+
+    /// State of the toggle bit, for protocols that have one
+    fn toggle(&self) -> Option<bool> {
+        None
+    }
+
+    /// Set the toggle bit, for protocols that have one
+    fn set_toggle(&mut self, _toggle: bool) {}
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// Protocol
 pub enum Protocol {
     Nec,
@@ -28,3 +41,52 @@ pub enum Protocol {
     Sbp,
     Unknown,
 }
+
+impl Protocol {
+    /// The carrier the protocol is modulated with, None if not known
+    pub const fn carrier(&self) -> Option<Carrier> {
+        use Protocol::*;
+
+        match self {
+            Nec | Nec16 | NecSamsung | Sbp => Some(Carrier::new(38_000, 33)),
+            Rc5 | Rc6 => Some(Carrier::new(36_000, 25)),
+            Unknown => None,
+        }
+    }
+
+    /// Time from the start of one frame to the start of the next when a command is repeated,
+    /// in microseconds. None if not known.
+    pub const fn frame_period(&self) -> Option<u32> {
+        use Protocol::*;
+
+        match self {
+            Nec | Nec16 | NecSamsung | Sbp => Some(108_000),
+            Rc5 => Some(113_778),
+            Rc6 => Some(106_667),
+            Unknown => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Carrier
+pub struct Carrier {
+    /// Frequency in Hz
+    pub frequency: u32,
+    /// Recommended duty cycle in percent
+    pub duty_cycle: u8,
+}
+
+impl Carrier {
+    pub const fn new(frequency: u32, duty_cycle: u8) -> Self {
+        Self {
+            frequency,
+            duty_cycle,
+        }
+    }
+
+    /// Duty value for a pwm with a maximum duty of `max_duty`
+    pub const fn duty(&self, max_duty: u32) -> u32 {
+        (max_duty as u64 * self.duty_cycle as u64 / 100) as u32
+    }
+}
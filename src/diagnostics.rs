@@ -0,0 +1,152 @@
+//! Per frame timing diagnostics
+//!
+//! With the `diagnostics` feature the protocol state machines measure every symbol of the
+//! frame they receive. The result of the last frame, decoded or not, is available from
+//! `ReceiverSM::diagnostics`, for example `recv.sm.diagnostics()`.
+
+use core::convert::TryFrom;
+
+/// Max number of symbol classes of a protocol
+const MAX_CLASSES: usize = 6;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+/// Measured durations of one class of symbols, in micro seconds
+pub struct SymbolStats {
+    /// Nominal duration
+    pub nominal: u32,
+    /// Shortest accepted duration
+    pub min: u32,
+    /// Longest accepted duration
+    pub max: u32,
+    /// Number of symbols received
+    pub count: u32,
+    /// Shortest received
+    pub shortest: u32,
+    /// Longest received
+    pub longest: u32,
+}
+
+impl SymbolStats {
+    #[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+    const fn new(nominal: u32, min: u32, max: u32) -> Self {
+        Self {
+            nominal,
+            min,
+            max,
+            count: 0,
+            shortest: 0,
+            longest: 0,
+        }
+    }
+
+    /// Largest deviation from the nominal duration
+    pub fn max_deviation(&self) -> u32 {
+        if self.count == 0 {
+            return 0;
+        }
+        self.nominal
+            .saturating_sub(self.shortest)
+            .max(self.longest.saturating_sub(self.nominal))
+    }
+
+    /// Distance from the received durations to the closest limit of the accepted range
+    pub fn margin(&self) -> u32 {
+        if self.count == 0 {
+            return self.nominal - self.min;
+        }
+        self.shortest
+            .saturating_sub(self.min)
+            .min(self.max.saturating_sub(self.longest))
+    }
+
+    #[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+    fn add(&mut self, dt: u32) {
+        if self.count == 0 {
+            self.shortest = dt;
+            self.longest = dt;
+        } else {
+            self.shortest = self.shortest.min(dt);
+            self.longest = self.longest.max(dt);
+        }
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+/// Timing diagnostics of a frame
+pub struct Diagnostics {
+    /// Nominal unit time of the protocol, in micro seconds
+    pub unit: u32,
+    classes: [SymbolStats; MAX_CLASSES],
+    nclasses: usize,
+    /// Number of symbols received
+    pub symbols: usize,
+    /// Index of the first symbol that didn't match any class
+    pub failed: Option<usize>,
+    /// Sum of the nominal and the measured durations of the matched symbols
+    nominal_sum: u64,
+    measured_sum: u64,
+}
+
+impl Diagnostics {
+    /// Diagnostics for a protocol with the unit time `unit` and symbol classes of
+    /// `(nominal, min, max)` durations
+    #[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+    pub(crate) fn new(unit: u32, classes: &[(u32, u32, u32)]) -> Self {
+        let mut diag = Self {
+            unit,
+            nclasses: classes.len().min(MAX_CLASSES),
+            ..Self::default()
+        };
+
+        for (stats, &(nominal, min, max)) in diag.classes.iter_mut().zip(classes) {
+            *stats = SymbolStats::new(nominal, min, max);
+        }
+
+        diag
+    }
+
+    /// Start of a new frame
+    #[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+    pub(crate) fn start(&mut self) {
+        for stats in &mut self.classes {
+            *stats = SymbolStats::new(stats.nominal, stats.min, stats.max);
+        }
+        self.symbols = 0;
+        self.failed = None;
+        self.nominal_sum = 0;
+        self.measured_sum = 0;
+    }
+
+    /// A symbol of `dt` micro seconds was received, matching `class`
+    #[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+    pub(crate) fn symbol(&mut self, class: Option<usize>, dt: u32) {
+        match class.and_then(|class| self.classes[..self.nclasses].get_mut(class)) {
+            Some(stats) => {
+                stats.add(dt);
+                self.nominal_sum += u64::from(stats.nominal);
+                self.measured_sum += u64::from(dt);
+            }
+            None => {
+                self.failed.get_or_insert(self.symbols);
+            }
+        }
+        self.symbols += 1;
+    }
+
+    /// Statistics for each symbol class of the protocol
+    pub fn classes(&self) -> &[SymbolStats] {
+        &self.classes[..self.nclasses]
+    }
+
+    /// Unit time measured over the matched symbols, in micro seconds
+    ///
+    /// A remote that runs fast or slow shows up as a measured unit off from the nominal.
+    pub fn measured_unit(&self) -> u32 {
+        if self.nominal_sum == 0 {
+            return 0;
+        }
+        u32::try_from(u64::from(self.unit) * self.measured_sum / self.nominal_sum)
+            .unwrap_or(u32::MAX)
+    }
+}
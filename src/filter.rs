@@ -0,0 +1,263 @@
+//! Glitch filter
+
+use crate::recv::{ReceiverSM, State};
+
+/// Receiver state machine wrapper that drops pulses shorter than `MIN_US` micro seconds
+///
+/// A dropped pulse is merged with the levels on both sides of it. The wrapper works with every
+/// receiver type:
+///
+/// ```
+/// # #[cfg(feature = "rc5")] {
+/// use infrared::{protocols::Rc5, EventReceiver, GlitchFilter};
+///
+/// let recv: EventReceiver<GlitchFilter<Rc5, 100>> = EventReceiver::new(40_000);
+/// # }
+/// ```
+///
+/// Each edge is passed on to `SM` when the next one shows that the level it ended was not a
+/// glitch. The last edge of a frame is passed on by the next edge, or by the idle timeout of the
+/// receiver.
+pub struct GlitchFilter<SM, const MIN_US: u32> {
+    sm: SM,
+    /// Edge not yet passed on, and the duration of the level it ended
+    pending: Option<(bool, u32)>,
+    /// The last level was a glitch, the next edge continues the pending level
+    merging: bool,
+    /// State of the wrapped state machine
+    state: State,
+    /// Glitches dropped, for the receiver statistics
+    #[cfg(feature = "stats")]
+    glitches: u32,
+}
+
+impl<SM: ReceiverSM, const MIN_US: u32> GlitchFilter<SM, MIN_US> {
+    /// The wrapped state machine
+    pub fn inner(&self) -> &SM {
+        &self.sm
+    }
+}
+
+impl<SM: ReceiverSM, const MIN_US: u32> ReceiverSM for GlitchFilter<SM, MIN_US> {
+    type Cmd = SM::Cmd;
+    type InternalState = State;
+
+    const MAX_GAP: u32 = SM::MAX_GAP;
+
+    fn create() -> Self {
+        Self {
+            sm: SM::create(),
+            pending: None,
+            merging: false,
+            state: State::Idle,
+            #[cfg(feature = "stats")]
+            glitches: 0,
+        }
+    }
+
+    fn event(&mut self, edge: bool, dt: u32) -> State {
+        let pending = match self.pending {
+            Some(pending) => pending,
+            None => {
+                self.pending = Some((edge, dt));
+                return State::Receiving;
+            }
+        };
+
+        if self.merging {
+            // Back at the level before the glitch
+            self.merging = false;
+            self.pending = Some((pending.0, pending.1 + dt));
+            return State::Receiving;
+        }
+
+        if dt < MIN_US {
+            // The level that just ended was a glitch
+            #[cfg(feature = "stats")]
+            {
+                self.glitches = self.glitches.wrapping_add(1);
+            }
+            self.merging = true;
+            self.pending = Some((pending.0, pending.1 + dt));
+            return State::Receiving;
+        }
+
+        self.pending = Some((edge, dt));
+        self.state = self.sm.event(pending.0, pending.1).into();
+
+        match self.state {
+            // Still waiting for the pending edge
+            State::Idle => State::Receiving,
+            state => state,
+        }
+    }
+
+    fn command(&self) -> Option<Self::Cmd> {
+        self.sm.command()
+    }
+
+    /// Reset the wrapped state machine. The pending edge is kept, it could be the start of the
+    /// next frame.
+    fn reset(&mut self) {
+        self.sm.reset();
+        self.state = State::Idle;
+    }
+
+    fn timeout(&mut self) -> Option<Self::Cmd> {
+        self.merging = false;
+
+        if let Some((edge, dt)) = self.pending.take() {
+            self.state = self.sm.event(edge, dt).into();
+
+            if self.state == State::Done {
+                let cmd = self.sm.command();
+                self.reset();
+                return cmd;
+            }
+        }
+
+        self.state = State::Idle;
+        self.sm.timeout()
+    }
+
+    #[cfg(feature = "diagnostics")]
+    fn diagnostics(&self) -> Option<&crate::Diagnostics> {
+        self.sm.diagnostics()
+    }
+
+    #[cfg(feature = "stats")]
+    fn take_glitches(&mut self) -> u32 {
+        core::mem::take(&mut self.glitches)
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+pub(crate) mod tests {
+    use std::vec::Vec;
+
+    use super::GlitchFilter;
+    use crate::recv::{BufferedReceiver, ReceiverSM};
+    use crate::sender::PulseSender;
+    use crate::Command;
+
+    /// `dists` with every `every`th level split in two by a one sample glitch, followed by an
+    /// idle gap that lets the filter pass on the last edge. Returns the samples and the number
+    /// of glitches.
+    fn with_glitches(dists: &[u32], every: usize) -> (Vec<u32>, u32) {
+        let mut noisy = Vec::new();
+        let mut glitches = 0;
+        for (i, &d) in dists.iter().chain(&[2000]).enumerate() {
+            if i % every == every - 1 && d >= 4 {
+                noisy.extend_from_slice(&[d / 2, 1, d - d / 2 - 1]);
+                glitches += 1;
+            } else {
+                noisy.push(d);
+            }
+        }
+        (noisy, glitches)
+    }
+
+    /// Two frames of `cmd` sampled at 40 kHz, each after an idle gap, with glitches
+    fn noisy_frames<CMD, S: PulseSender<CMD>>(cmd: &CMD, every: usize) -> (Vec<u32>, u32) {
+        let idle = 2000;
+        let mut pulses = [0u32; 80];
+        let n = S::pulses_ticks(cmd, &mut pulses, 40_000).unwrap();
+
+        let mut dists = Vec::new();
+        for _ in 0..2 {
+            dists.push(idle);
+            dists.extend_from_slice(&pulses[..n]);
+        }
+
+        with_glitches(&dists, every)
+    }
+
+    /// The glitches in `noisy`, two frames of `expected` sampled at 40 kHz, break the frames
+    /// unless they are filtered
+    #[cfg_attr(not(feature = "stats"), allow(unused_variables))]
+    fn filters_glitches<SM: ReceiverSM>(noisy: (Vec<u32>, u32), expected: (u32, u32)) {
+        let (dists, glitches) = noisy;
+
+        let brecv: BufferedReceiver<SM> = BufferedReceiver::new(&dists, 40_000);
+        assert!(brecv.count() < 2);
+
+        let brecv: BufferedReceiver<GlitchFilter<SM, 100>> = BufferedReceiver::new(&dists, 40_000);
+        let cmds: Vec<_> = brecv.map(|cmd| (cmd.address(), cmd.data())).collect();
+        assert_eq!(cmds, [expected, expected]);
+
+        #[cfg(feature = "stats")]
+        {
+            let mut recv: crate::EventReceiver<GlitchFilter<SM, 100>> =
+                crate::EventReceiver::new(40_000);
+            for (i, dt) in dists.iter().enumerate() {
+                let _ = recv.edge_event(i & 0x1 == 0, *dt);
+            }
+
+            let stats = recv.stats();
+            assert_eq!((stats.frames, stats.glitches), (2, glitches));
+        }
+    }
+
+    /// Inject glitches into `capture`, two captured frames of `expected` at 40 kHz, and decode
+    /// it through the filter
+    #[cfg(any(feature = "nec", feature = "rc5", feature = "rc6"))]
+    pub(crate) fn filters_capture_glitches<SM: ReceiverSM>(
+        capture: &[u32],
+        every: usize,
+        expected: (u32, u32),
+    ) {
+        // The captures record the idle gap before a frame as 0, which the filter would take for
+        // a glitch
+        let capture: Vec<u32> = capture
+            .iter()
+            .map(|&d| if d == 0 { 2000 } else { d })
+            .collect();
+
+        filters_glitches::<SM>(with_glitches(&capture, every), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "nec")]
+    fn nec() {
+        use crate::protocols::nec::{Nec, NecCommand, NecStandard, NecTypeSender};
+
+        let cmd: NecCommand = NecCommand::new(7, 44);
+        let noisy = noisy_frames::<_, NecTypeSender<NecStandard>>(&cmd, 7);
+        filters_glitches::<Nec>(noisy, (7, 44));
+    }
+
+    #[test]
+    #[cfg(feature = "rc5")]
+    fn rc5() {
+        use crate::protocols::rc5::{Rc5, Rc5Command, Rc5Sender};
+
+        let cmd = Rc5Command::new(20, 9, false);
+        let noisy = noisy_frames::<_, Rc5Sender>(&cmd, 5);
+        filters_glitches::<Rc5>(noisy, (20, 9));
+    }
+
+    #[test]
+    #[cfg(feature = "rc6")]
+    fn rc6() {
+        use crate::protocols::rc6::{Rc6, Rc6Cmd, Rc6Sender};
+
+        let cmd = Rc6Cmd::new(70, 2);
+        let noisy = noisy_frames::<_, Rc6Sender>(&cmd, 5);
+        filters_glitches::<Rc6>(noisy, (70, 2));
+    }
+
+    #[test]
+    #[cfg(feature = "sbp")]
+    fn sbp() {
+        use crate::protocols::sbp::{Sbp, SbpCommand, SbpSender};
+
+        let cmd = SbpCommand {
+            address: 0x1234,
+            command: 0x56,
+            valid: true,
+        };
+        let noisy = noisy_frames::<_, SbpSender>(&cmd, 7);
+        filters_glitches::<Sbp>(noisy, (0x1234, 0x56));
+    }
+}
@@ -3,7 +3,8 @@
 use embedded_hal::digital::v2::InputPin;
 
 use crate::recv::{self, ReceiverSM};
-use crate::remotecontrol::{Button, RemoteControl};
+#[cfg(feature = "remotes")]
+use crate::{Button, ButtonEvents, ButtonTracker, RemoteControl};
 
 /// Event driven Hal receiver
 pub struct EventReceiver<SM, PIN> {
@@ -43,6 +44,23 @@ where
             Err(_err) => Ok(None),
         }
     }
+
+    /// Check if the frame being received has timed out, `idle` samples after the last edge
+    pub fn timeout_check(&mut self, idle: u32) -> Option<SM::Cmd> {
+        self.recv.timeout_check(idle)
+    }
+
+    /// Snapshot of the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::Statistics {
+        self.recv.stats()
+    }
+
+    /// Reset the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.recv.reset_stats()
+    }
 }
 
 /// Periodic and polled Embedded hal Receiver
@@ -77,6 +95,18 @@ where
         self.pin
     }
 
+    /// Snapshot of the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::Statistics {
+        self.recv.stats()
+    }
+
+    /// Reset the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.recv.reset_stats()
+    }
+
     pub fn poll(&mut self) -> Result<Option<SM::Cmd>, PINERR> {
         let pinval = self.pin.is_low()?;
 
@@ -94,6 +124,36 @@ where
     ) -> Result<Option<Button>, PINERR> {
         self.poll().map(|cmd| cmd.and_then(RC::decode))
     }
+
+    /// Poll the receiver and update `tracker` with the received buttons
+    ///
+    /// `tracker` should be created with the samplerate of this receiver
+    #[cfg(feature = "remotes")]
+    pub fn poll_event<RC: RemoteControl<Cmd = SM::Cmd>>(
+        &mut self,
+        tracker: &mut ButtonTracker,
+    ) -> Result<ButtonEvents, PINERR> {
+        let cmd = self.poll()?;
+        let ts = self.counter;
+
+        let events = cmd.and_then(|cmd| {
+            let button = RC::decode_any(&cmd)?;
+            Some(tracker.command(&cmd, button, ts))
+        });
+
+        Ok(match events {
+            Some(events) if !events.is_empty() => events,
+            _ => tracker.poll(ts).into(),
+        })
+    }
+}
+
+/// The statistics type, once for each receiver
+#[cfg(feature = "stats")]
+macro_rules! statistics {
+    ($N:ident) => {
+        crate::Statistics
+    };
 }
 
 macro_rules! multireceiver {
@@ -127,6 +187,18 @@ macro_rules! multireceiver {
             self.pin
         }
 
+        /// Snapshot of the statistics counters of each protocol
+        #[cfg(feature = "stats")]
+        pub fn stats(&self) -> ( $( statistics!($N) ),* ) {
+            ( $( self.$N.stats() ),* )
+        }
+
+        /// Reset the statistics counters
+        #[cfg(feature = "stats")]
+        pub fn reset_stats(&mut self) {
+            $( self.$N.reset_stats(); )*
+        }
+
         pub fn poll(&mut self) -> Result<( $( Option<$C>),*), PINERR> {
             let pinval = self.pin.is_low()?;
             self.counter = self.counter.wrapping_add(1);
@@ -0,0 +1,256 @@
+//! Embedded-hal 1.0 based Receiver types
+
+use eh1::digital::InputPin;
+use eh1::pwm::SetDutyCycle;
+
+use crate::recv::{self, ReceiverSM};
+#[cfg(feature = "remotes")]
+use crate::remotecontrol::{Button, RemoteControl};
+use crate::sender::{Sender, State};
+
+/// Event driven Hal receiver
+pub struct EventReceiver<SM, PIN> {
+    recv: recv::EventReceiver<SM>,
+    pub pin: PIN,
+}
+
+impl<PIN, SM> EventReceiver<SM, PIN>
+where
+    SM: ReceiverSM,
+    PIN: InputPin,
+{
+    /// Create a new EventReceiver
+    /// `pin`: The Inputpin connected to the receiver,
+    /// `samplerate`: Sample rate of the receiver
+    pub fn new(pin: PIN, samplerate: u32) -> Self {
+        Self {
+            recv: recv::EventReceiver::new(samplerate),
+            pin,
+        }
+    }
+
+    /// Destroy Receiver and hand back pin
+    pub fn destroy(self) -> PIN {
+        self.pin
+    }
+
+    /// Tell the receiver to read the new pin value and update the receiver state machine
+    ///
+    /// Returns Ok(None) until a command is detected
+    #[inline(always)]
+    pub fn edge_event(&mut self, dt: u32) -> Result<Option<SM::Cmd>, PIN::Error> {
+        let pinval = self.pin.is_low()?;
+
+        match self.recv.edge_event(pinval, dt) {
+            Ok(cmd) => Ok(cmd),
+            Err(_err) => Ok(None),
+        }
+    }
+
+    /// Check if the frame being received has timed out, `idle` samples after the last edge
+    pub fn timeout_check(&mut self, idle: u32) -> Option<SM::Cmd> {
+        self.recv.timeout_check(idle)
+    }
+
+    /// Snapshot of the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::Statistics {
+        self.recv.stats()
+    }
+
+    /// Reset the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.recv.reset_stats()
+    }
+}
+
+/// Periodic and polled Embedded hal Receiver
+///
+/// The poll methods should be called periodically for this receiver to work
+pub struct PeriodicReceiver<SM, PIN> {
+    /// The receiver state machine
+    recv: recv::PeriodicReceiver<SM>,
+    /// Input pin
+    pin: PIN,
+    /// Internal sample counter
+    counter: u32,
+}
+
+impl<PIN, SM> PeriodicReceiver<SM, PIN>
+where
+    SM: ReceiverSM,
+    PIN: InputPin,
+{
+    /// Create a new PeriodicReceiver
+    /// `pin` : The gpio pin the hw is connected to
+    /// `samplerate` : Rate of which you intend to call poll.
+    pub fn new(pin: PIN, samplerate: u32) -> Self {
+        Self {
+            recv: recv::PeriodicReceiver::new(samplerate),
+            pin,
+            counter: 0,
+        }
+    }
+
+    pub fn destroy(self) -> PIN {
+        self.pin
+    }
+
+    /// Snapshot of the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::Statistics {
+        self.recv.stats()
+    }
+
+    /// Reset the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.recv.reset_stats()
+    }
+
+    pub fn poll(&mut self) -> Result<Option<SM::Cmd>, PIN::Error> {
+        let pinval = self.pin.is_low()?;
+
+        self.counter = self.counter.wrapping_add(1);
+
+        match self.recv.poll(pinval, self.counter) {
+            Ok(cmd) => Ok(cmd),
+            Err(_err) => Ok(None),
+        }
+    }
+
+    #[cfg(feature = "remotes")]
+    pub fn poll_button<RC: RemoteControl<Cmd = SM::Cmd>>(
+        &mut self,
+    ) -> Result<Option<Button>, PIN::Error> {
+        self.poll().map(|cmd| cmd.and_then(RC::decode))
+    }
+}
+
+/// Sender with output on a pwm channel, running at the carrier frequency
+pub trait DutyCycleSender<CMD>: Sender<CMD> {
+    /// Step the transmit loop and output on `pwm`
+    ///
+    /// The duty cycle is set to the recommended one for the carrier during marks, and to zero
+    /// otherwise.
+    fn step_duty<PWM: SetDutyCycle>(
+        &mut self,
+        ts: u32,
+        pwm: &mut PWM,
+    ) -> Result<State, PWM::Error> {
+        let state = self.step(ts);
+        match state {
+            State::Transmit(true) => pwm.set_duty_cycle_percent(self.carrier().duty_cycle)?,
+            _ => pwm.set_duty_cycle_fully_off()?,
+        }
+        Ok(state)
+    }
+}
+
+impl<CMD, S: Sender<CMD>> DutyCycleSender<CMD> for S {}
+
+#[cfg(test)]
+#[cfg(feature = "nec")]
+mod tests {
+    use core::cell::Cell;
+    use core::convert::Infallible;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    use eh1::digital::{self, InputPin};
+    use eh1::pwm::{self, SetDutyCycle};
+
+    use super::{DutyCycleSender, PeriodicReceiver};
+    use crate::protocols::nec::{self, NecCommand, NecStandard, NecTypeSender};
+    use crate::protocols::Nec;
+    use crate::sender::{Sender, State};
+
+    /// Pin with the level set by the test, active low like the output of a receiver
+    struct Level(Rc<Cell<bool>>);
+
+    impl digital::ErrorType for Level {
+        type Error = Infallible;
+    }
+
+    impl InputPin for Level {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(self.0.get())
+        }
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(!self.0.get())
+        }
+    }
+
+    /// Pwm channel recording the duty cycles set
+    struct Pwm(Vec<u16>);
+
+    impl pwm::ErrorType for Pwm {
+        type Error = Infallible;
+    }
+
+    impl SetDutyCycle for Pwm {
+        fn max_duty_cycle(&self) -> u16 {
+            1000
+        }
+        fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Infallible> {
+            self.0.push(duty);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn poll() {
+        let samplerate = 20_000;
+        let pulses = nec::tests::frame();
+
+        let level = Rc::new(Cell::new(true));
+        let mut recv: PeriodicReceiver<Nec, _> =
+            PeriodicReceiver::new(Level(level.clone()), samplerate);
+        let mut cmds = Vec::new();
+
+        // Idle, the frame, and idle again, one poll per sample
+        let mut levels = Vec::new();
+        levels.extend(core::iter::repeat(true).take(100));
+        for (i, dt) in pulses.iter().enumerate() {
+            let samples = (dt * samplerate / 1_000_000) as usize;
+            levels.extend(core::iter::repeat(i % 2 == 1).take(samples));
+        }
+        levels.extend(core::iter::repeat(true).take(100));
+
+        for high in levels {
+            level.set(high);
+            if let Some(cmd) = recv.poll().unwrap() {
+                cmds.push(cmd);
+            }
+        }
+
+        assert_eq!(cmds.len(), 1);
+        assert_eq!((cmds[0].addr, cmds[0].cmd), (7, 44));
+    }
+
+    #[test]
+    fn duty_cycle() {
+        let mut sender: NecTypeSender<NecStandard> = NecTypeSender::new(1_000_000);
+        let mut pwm = Pwm(Vec::new());
+
+        sender.load(NecCommand::new(7, 44));
+
+        let mut ts = 0;
+        while !matches!(sender.step_duty(ts, &mut pwm).unwrap(), State::Idle) {
+            ts += 1;
+        }
+
+        // 33% of the max duty during the marks, off otherwise
+        assert!(pwm.0.iter().all(|&duty| duty == 330 || duty == 0));
+        assert_eq!(pwm.0[0], 330);
+
+        let marks = pwm
+            .0
+            .windows(2)
+            .filter(|w| w[0] == 0 && w[1] == 330)
+            .count()
+            + 1;
+        assert_eq!(marks, 34);
+    }
+}
@@ -0,0 +1,242 @@
+//! Async receiver, awaiting edges with embedded-hal-async
+
+use eh1::digital::InputPin;
+use embedded_hal_async::digital::Wait;
+
+use crate::recv::{self, ReceiverSM};
+
+/// Monotonic clock, used to timestamp the edges
+pub trait Clock {
+    /// Current time in ticks. Allowed to wrap.
+    fn now(&mut self) -> u32;
+}
+
+impl<F: FnMut() -> u32> Clock for F {
+    fn now(&mut self) -> u32 {
+        self()
+    }
+}
+
+/// Receiver that awaits the edges on `PIN` and timestamps them with `CLOCK`
+pub struct AsyncReceiver<SM, PIN, CLOCK> {
+    recv: recv::EventReceiver<SM>,
+    pin: PIN,
+    clock: CLOCK,
+    /// Timestamp of the last edge
+    last: u32,
+}
+
+impl<SM, PIN, CLOCK> AsyncReceiver<SM, PIN, CLOCK>
+where
+    SM: ReceiverSM,
+    PIN: InputPin + Wait,
+    CLOCK: Clock,
+{
+    /// Create a new receiver
+    /// `pin`: The pin connected to the receiver
+    /// `clock`: Monotonic clock
+    /// `tickrate`: Frequency of `clock`
+    pub fn new(pin: PIN, clock: CLOCK, tickrate: u32) -> Self {
+        Self {
+            recv: recv::EventReceiver::new(tickrate),
+            pin,
+            clock,
+            last: 0,
+        }
+    }
+
+    /// Hand back the pin and the clock
+    pub fn destroy(self) -> (PIN, CLOCK) {
+        (self.pin, self.clock)
+    }
+
+    /// Wait for the next command
+    ///
+    /// Call in a loop to receive a stream of commands. Decode errors are skipped.
+    pub async fn receive(&mut self) -> Result<SM::Cmd, PIN::Error> {
+        loop {
+            self.pin.wait_for_any_edge().await?;
+
+            let now = self.clock.now();
+            let dt = now.wrapping_sub(self.last);
+            self.last = now;
+
+            let pinval = self.pin.is_low()?;
+
+            if let Ok(Some(cmd)) = self.recv.edge_event(pinval, dt) {
+                return Ok(cmd);
+            }
+        }
+    }
+
+    /// Check if the frame being received has timed out, reading the time from the clock
+    ///
+    /// `receive` only wakes up on edges. Call this when no edge has arrived for a while, for
+    /// example by racing `receive` against a timer, to drop a truncated frame.
+    pub fn timeout_check(&mut self) -> Option<SM::Cmd> {
+        let idle = self.clock.now().wrapping_sub(self.last);
+        self.recv.timeout_check(idle)
+    }
+
+    /// Snapshot of the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::Statistics {
+        self.recv.stats()
+    }
+
+    /// Reset the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.recv.reset_stats()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "nec")]
+mod tests {
+    use core::cell::{Cell, RefCell};
+    use core::convert::Infallible;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    use eh1::digital::{ErrorType, InputPin};
+    use embedded_hal_async::digital::Wait;
+
+    use super::AsyncReceiver;
+    use crate::protocols::{nec, Nec};
+
+    /// Pin replaying edges at the given timestamps, waiting forever when out of edges
+    struct Replay {
+        edges: Rc<RefCell<VecDeque<(bool, u32)>>>,
+        low: bool,
+        clock: Rc<Cell<u32>>,
+    }
+
+    impl Replay {
+        async fn next_edge(&mut self) {
+            let edge = self.edges.borrow_mut().pop_front();
+            match edge {
+                Some((low, ts)) => {
+                    self.low = low;
+                    self.clock.set(ts);
+                }
+                None => core::future::pending().await,
+            }
+        }
+    }
+
+    impl ErrorType for Replay {
+        type Error = Infallible;
+    }
+
+    impl InputPin for Replay {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(!self.low)
+        }
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(self.low)
+        }
+    }
+
+    impl Wait for Replay {
+        async fn wait_for_high(&mut self) -> Result<(), Infallible> {
+            while self.low {
+                self.next_edge().await;
+            }
+            Ok(())
+        }
+        async fn wait_for_low(&mut self) -> Result<(), Infallible> {
+            while !self.low {
+                self.next_edge().await;
+            }
+            Ok(())
+        }
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Infallible> {
+            self.next_edge().await;
+            self.wait_for_high().await
+        }
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Infallible> {
+            self.next_edge().await;
+            self.wait_for_low().await
+        }
+        async fn wait_for_any_edge(&mut self) -> Result<(), Infallible> {
+            self.next_edge().await;
+            Ok(())
+        }
+    }
+
+    fn poll_once<F: Future>(fut: F) -> Option<F::Output> {
+        let mut fut = pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(out) => Some(out),
+            Poll::Pending => None,
+        }
+    }
+
+    /// Edges of a Nec frame starting at `ts`, the output of the receiver is active low
+    fn nec_edges(ts: u32) -> Vec<(bool, u32)> {
+        let pulses = nec::tests::frame();
+
+        let mut ts = ts;
+        let mut edges = Vec::new();
+        for (i, dt) in pulses.iter().enumerate() {
+            edges.push((i & 0x1 == 0, ts));
+            ts += dt;
+        }
+        edges.push((false, ts));
+        edges
+    }
+
+    fn replay(edges: &Rc<RefCell<VecDeque<(bool, u32)>>>, clock: &Rc<Cell<u32>>) -> Replay {
+        Replay {
+            edges: edges.clone(),
+            low: false,
+            clock: clock.clone(),
+        }
+    }
+
+    #[test]
+    fn receive() {
+        let edges = Rc::new(RefCell::new(nec_edges(1000).into()));
+        let clock = Rc::new(Cell::new(0));
+        let pin = replay(&edges, &clock);
+
+        let mut recv: AsyncReceiver<Nec, _, _> =
+            AsyncReceiver::new(pin, move || clock.get(), 1_000_000);
+
+        let cmd = poll_once(recv.receive()).unwrap().unwrap();
+        assert_eq!((cmd.addr, cmd.cmd), (7, 44));
+    }
+
+    #[test]
+    fn timeout() {
+        let frame = nec_edges(1000);
+        let edges = Rc::new(RefCell::new(frame[..20].iter().copied().collect()));
+        let clock = Rc::new(Cell::new(0));
+        let pin = replay(&edges, &clock);
+
+        let now = clock.clone();
+        let mut recv: AsyncReceiver<Nec, _, _> =
+            AsyncReceiver::new(pin, move || now.get(), 1_000_000);
+
+        // The edges of a truncated frame, then nothing
+        assert!(poll_once(recv.receive()).is_none());
+
+        clock.set(frame[19].1 + 1000);
+        assert!(recv.timeout_check().is_none());
+        clock.set(frame[19].1 + 100_000);
+        assert!(recv.timeout_check().is_none());
+
+        #[cfg(feature = "stats")]
+        assert_eq!(recv.stats().timeouts, 1);
+
+        edges.borrow_mut().extend(nec_edges(200_000));
+        let cmd = poll_once(recv.receive()).unwrap().unwrap();
+        assert_eq!((cmd.addr, cmd.cmd), (7, 44));
+    }
+}
@@ -0,0 +1,194 @@
+//! Button to USB HID usage mapping
+//!
+//! Usage ids are from the USB HID Usage Tables, Consumer Page (0x0C) and
+//! Keyboard/Keypad Page (0x07).
+
+use crate::Button;
+
+/// Consumer Page
+pub const CONSUMER_PAGE: u16 = 0x0C;
+/// Keyboard/Keypad Page
+pub const KEYBOARD_PAGE: u16 = 0x07;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// The type of report a usage is sent in
+pub enum ReportType {
+    /// Consumer control report, with a 16 bit usage id
+    Consumer,
+    /// Keyboard report, with 8 bit keycodes
+    Keyboard,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// USB HID usage
+pub enum Usage {
+    /// Consumer Page usage
+    Consumer(u16),
+    /// Keyboard/Keypad Page usage
+    Keyboard(u8),
+}
+
+impl Usage {
+    /// Usage page
+    pub const fn page(&self) -> u16 {
+        match self {
+            Usage::Consumer(_) => CONSUMER_PAGE,
+            Usage::Keyboard(_) => KEYBOARD_PAGE,
+        }
+    }
+
+    /// Usage id
+    pub const fn id(&self) -> u16 {
+        match *self {
+            Usage::Consumer(id) => id,
+            Usage::Keyboard(id) => id as u16,
+        }
+    }
+
+    /// The report the usage is sent in
+    pub const fn report(&self) -> ReportType {
+        match self {
+            Usage::Consumer(_) => ReportType::Consumer,
+            Usage::Keyboard(_) => ReportType::Keyboard,
+        }
+    }
+}
+
+impl Button {
+    /// USB HID usage for the button, if there is a fitting one
+    pub const fn hid_usage(self) -> Option<Usage> {
+        use Button::*;
+        use Usage::*;
+
+        let usage = match self {
+            Power => Consumer(0x30),
+            Setup => Consumer(0x40),
+            Info => Consumer(0x60),
+            Subtitle => Consumer(0x61),
+            Red => Consumer(0x69),
+            Green => Consumer(0x6A),
+            Blue => Consumer(0x6B),
+            Yellow => Consumer(0x6C),
+            PictureSize => Consumer(0x6D),
+            Mode => Consumer(0x82),
+            ChannelPrev => Consumer(0x83),
+            ChannelList => Consumer(0x8D),
+            Emanual => Consumer(0x95),
+            ChannelListNext => Consumer(0x9C),
+            ChannelListPrev => Consumer(0x9D),
+            Play => Consumer(0xB0),
+            Paus => Consumer(0xB1),
+            Forward => Consumer(0xB3),
+            Rewind => Consumer(0xB4),
+            Next => Consumer(0xB5),
+            Prev => Consumer(0xB6),
+            Stop => Consumer(0xB7),
+            Random | Shuffle => Consumer(0xB9),
+            Repeat => Consumer(0xBC),
+            Play_Paus => Consumer(0xCD),
+            Mute | VolumeMute => Consumer(0xE2),
+            VolumeUp | Plus => Consumer(0xE9),
+            VolumeDown | Minus => Consumer(0xEA),
+            Return => Consumer(0x224),
+
+            One => Keyboard(0x1E),
+            Two => Keyboard(0x1F),
+            Three => Keyboard(0x20),
+            Four => Keyboard(0x21),
+            Five => Keyboard(0x22),
+            Six => Keyboard(0x23),
+            Seven => Keyboard(0x24),
+            Eight => Keyboard(0x25),
+            Nine => Keyboard(0x26),
+            Zero => Keyboard(0x27),
+            Enter => Keyboard(0x28),
+            Exit => Keyboard(0x29),
+            Right => Keyboard(0x4F),
+            Left => Keyboard(0x50),
+            Down => Keyboard(0x51),
+            Up => Keyboard(0x52),
+
+            _ => return None,
+        };
+
+        Some(usage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReportType, Usage};
+    use crate::Button;
+
+    #[test]
+    fn media_usages() {
+        let table = [
+            (Button::Power, 0x30),
+            (Button::Play, 0xB0),
+            (Button::Paus, 0xB1),
+            (Button::Play_Paus, 0xCD),
+            (Button::Stop, 0xB7),
+            (Button::Forward, 0xB3),
+            (Button::Rewind, 0xB4),
+            (Button::Next, 0xB5),
+            (Button::Prev, 0xB6),
+            (Button::Shuffle, 0xB9),
+            (Button::Random, 0xB9),
+            (Button::Repeat, 0xBC),
+            (Button::Mute, 0xE2),
+            (Button::VolumeMute, 0xE2),
+            (Button::VolumeUp, 0xE9),
+            (Button::Plus, 0xE9),
+            (Button::VolumeDown, 0xEA),
+            (Button::Minus, 0xEA),
+            (Button::ChannelPrev, 0x83),
+            (Button::Return, 0x224),
+        ];
+
+        for (button, id) in table.iter().copied() {
+            let usage = button.hid_usage();
+            assert_eq!(usage, Some(Usage::Consumer(id)), "{:?}", button);
+            assert_eq!(usage.map(|u| (u.page(), u.id())), Some((0x0C, id)));
+            assert_eq!(usage.map(|u| u.report()), Some(ReportType::Consumer));
+        }
+    }
+
+    #[test]
+    fn navigation_usages() {
+        let table = [
+            (Button::Up, 0x52),
+            (Button::Down, 0x51),
+            (Button::Left, 0x50),
+            (Button::Right, 0x4F),
+            (Button::Enter, 0x28),
+            (Button::Exit, 0x29),
+            (Button::One, 0x1E),
+            (Button::Nine, 0x26),
+            (Button::Zero, 0x27),
+        ];
+
+        for (button, id) in table.iter().copied() {
+            let usage = button.hid_usage();
+            assert_eq!(usage, Some(Usage::Keyboard(id)), "{:?}", button);
+            assert_eq!(
+                usage.map(|u| (u.page(), u.id())),
+                Some((0x07, u16::from(id)))
+            );
+            assert_eq!(usage.map(|u| u.report()), Some(ReportType::Keyboard));
+        }
+    }
+
+    #[test]
+    fn unmapped() {
+        for button in [
+            Button::Source,
+            Button::Teletext,
+            Button::Tools,
+            Button::U_SD,
+        ]
+        .iter()
+        {
+            assert_eq!(button.hid_usage(), None);
+        }
+    }
+}
@@ -21,13 +21,16 @@
 //! +------+--------+------------+-----+-----+-----+
 //! ```
 //!
+//! For timers with input capture, `CaptureReceiver` decodes chunks of captured timestamps, for
+//! example from a DMA ring buffer.
+//!
 //! ## Using Infrared with embedded-hal
 //!
 //! ### Polled
 //!
 //! Right now, this is the easiest way to setup Infrared to work with any embedded-hal based board.
 //! 1. Setup a CountDown-timer at a frequency of something like 20 kHz. How to setup the timer
-//! and enable interrupts is HAL-specific but most HALs have examples showing you how to do it.
+//!    and enable interrupts is HAL-specific but most HALs have examples showing you how to do it.
 //!
 //! 2. Create a `hal::PeriodicReceiver` with the desired Decoder state machine.
 //!
@@ -42,7 +45,7 @@
 //! ```
 //!
 //! 3. In the timer interrupt handler for the timer `poll` the receiver and wait for it to
-//! successfully detect a command
+//!    successfully detect a command
 //!
 //! ```ignore
 //! if let Ok(Some(cmd)) = recv.poll() {
@@ -64,6 +67,21 @@
 //! }
 //! ```
 //!
+//! To know when a button is pressed, held or released, let a `ButtonTracker` keep track of the
+//! buttons
+//!
+//! ```ignore
+//! use infrared::{ButtonEvent, ButtonTracker};
+//!
+//! let mut tracker = ButtonTracker::new(SAMPLERATE);
+//!
+//! for event in recv.poll_event::<Rc5CdPlayer>(&mut tracker)? {
+//!     if let ButtonEvent::LongPress(Button::Power) = event {
+//!         ... // Power held down
+//!     }
+//! }
+//! ```
+//!
 //! #### Evented
 //!
 //! The library could also be used with external interrupt if you have a way of keeping track
@@ -94,19 +112,64 @@ extern crate std;
 pub mod protocols;
 
 mod recv;
-pub use recv::{BufferedReceiver, EventReceiver, PeriodicReceiver, ReceiverSM};
+pub use recv::{
+    BufferedReceiver, DurationReceiver, EventReceiver, PeriodicReceiver, ReceiverSM, TickConverter,
+};
+
+mod capture;
+pub use capture::CaptureReceiver;
+
+mod filter;
+pub use filter::GlitchFilter;
+
+mod rawcapture;
+pub use rawcapture::RawCapture;
+
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::{Diagnostics, SymbolStats};
+
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "stats")]
+pub use stats::Statistics;
 
 pub mod sender;
 
 mod remotecontrol;
-pub use remotecontrol::{Button, DeviceType, RemoteControl};
+#[doc(hidden)]
+pub use remotecontrol::check_buttons;
+pub use remotecontrol::{Button, ButtonMapping, DeviceType, RemoteControl};
+
+mod buttonevent;
+pub use buttonevent::{ButtonEvent, ButtonEvents, ButtonTracker};
 
 mod cmd;
 #[doc(inline)]
-pub use cmd::{Command, Protocol};
+pub use cmd::{Carrier, Command, Protocol};
 
 #[cfg(feature = "remotes")]
 pub mod remotes;
 
+#[cfg(all(feature = "std", feature = "remotes"))]
+#[doc(hidden)]
+pub use linkme as __linkme;
+
 #[cfg(feature = "embedded-hal")]
 pub mod hal;
+
+#[cfg(feature = "embedded-hal-1")]
+pub mod hal1;
+
+#[cfg(feature = "async")]
+pub mod hal_async;
+
+#[cfg(feature = "hid")]
+pub mod hid;
+
+#[cfg(feature = "std")]
+pub mod analyze;
+
+#[cfg(feature = "std")]
+pub mod linux;
@@ -0,0 +1,279 @@
+//! Button to Linux input keycode mapping
+//!
+//! Keycodes are from `linux/input-event-codes.h`.
+
+use crate::Button;
+
+pub const KEY_1: u16 = 2;
+pub const KEY_2: u16 = 3;
+pub const KEY_3: u16 = 4;
+pub const KEY_4: u16 = 5;
+pub const KEY_5: u16 = 6;
+pub const KEY_6: u16 = 7;
+pub const KEY_7: u16 = 8;
+pub const KEY_8: u16 = 9;
+pub const KEY_9: u16 = 10;
+pub const KEY_0: u16 = 11;
+pub const KEY_ENTER: u16 = 28;
+pub const KEY_UP: u16 = 103;
+pub const KEY_LEFT: u16 = 105;
+pub const KEY_RIGHT: u16 = 106;
+pub const KEY_DOWN: u16 = 108;
+pub const KEY_MUTE: u16 = 113;
+pub const KEY_VOLUMEDOWN: u16 = 114;
+pub const KEY_VOLUMEUP: u16 = 115;
+pub const KEY_POWER: u16 = 116;
+pub const KEY_PAUSE: u16 = 119;
+pub const KEY_STOP: u16 = 128;
+pub const KEY_HELP: u16 = 138;
+pub const KEY_SETUP: u16 = 141;
+pub const KEY_BACK: u16 = 158;
+pub const KEY_NEXTSONG: u16 = 163;
+pub const KEY_PLAYPAUSE: u16 = 164;
+pub const KEY_PREVIOUSSONG: u16 = 165;
+pub const KEY_REWIND: u16 = 168;
+pub const KEY_EXIT: u16 = 174;
+pub const KEY_PLAY: u16 = 207;
+pub const KEY_FASTFORWARD: u16 = 208;
+pub const KEY_OPTION: u16 = 0x165;
+pub const KEY_INFO: u16 = 0x166;
+pub const KEY_TIME: u16 = 0x167;
+pub const KEY_PROGRAM: u16 = 0x16a;
+pub const KEY_SUBTITLE: u16 = 0x172;
+pub const KEY_MODE: u16 = 0x175;
+pub const KEY_ASPECT_RATIO: u16 = 0x177;
+pub const KEY_TEXT: u16 = 0x184;
+pub const KEY_LIST: u16 = 0x18b;
+pub const KEY_RED: u16 = 0x18e;
+pub const KEY_GREEN: u16 = 0x18f;
+pub const KEY_YELLOW: u16 = 0x190;
+pub const KEY_BLUE: u16 = 0x191;
+pub const KEY_CHANNELUP: u16 = 0x192;
+pub const KEY_CHANNELDOWN: u16 = 0x193;
+pub const KEY_LAST: u16 = 0x195;
+pub const KEY_SHUFFLE: u16 = 0x19a;
+
+/// All keycodes that buttons are mapped to
+pub const KEYCODES: &[u16] = &[
+    KEY_1,
+    KEY_2,
+    KEY_3,
+    KEY_4,
+    KEY_5,
+    KEY_6,
+    KEY_7,
+    KEY_8,
+    KEY_9,
+    KEY_0,
+    KEY_ENTER,
+    KEY_UP,
+    KEY_LEFT,
+    KEY_RIGHT,
+    KEY_DOWN,
+    KEY_MUTE,
+    KEY_VOLUMEDOWN,
+    KEY_VOLUMEUP,
+    KEY_POWER,
+    KEY_PAUSE,
+    KEY_STOP,
+    KEY_HELP,
+    KEY_SETUP,
+    KEY_BACK,
+    KEY_NEXTSONG,
+    KEY_PLAYPAUSE,
+    KEY_PREVIOUSSONG,
+    KEY_REWIND,
+    KEY_EXIT,
+    KEY_PLAY,
+    KEY_FASTFORWARD,
+    KEY_OPTION,
+    KEY_INFO,
+    KEY_TIME,
+    KEY_PROGRAM,
+    KEY_SUBTITLE,
+    KEY_MODE,
+    KEY_ASPECT_RATIO,
+    KEY_TEXT,
+    KEY_LIST,
+    KEY_RED,
+    KEY_GREEN,
+    KEY_YELLOW,
+    KEY_BLUE,
+    KEY_CHANNELUP,
+    KEY_CHANNELDOWN,
+    KEY_LAST,
+    KEY_SHUFFLE,
+];
+
+impl Button {
+    /// Linux input keycode for the button, if there is a fitting one
+    pub const fn linux_keycode(self) -> Option<u16> {
+        use Button::*;
+
+        let key = match self {
+            Power => KEY_POWER,
+            One => KEY_1,
+            Two => KEY_2,
+            Three => KEY_3,
+            Four => KEY_4,
+            Five => KEY_5,
+            Six => KEY_6,
+            Seven => KEY_7,
+            Eight => KEY_8,
+            Nine => KEY_9,
+            Zero => KEY_0,
+            Teletext => KEY_TEXT,
+            ChannelPrev => KEY_LAST,
+            VolumeUp | Plus => KEY_VOLUMEUP,
+            VolumeDown | Minus => KEY_VOLUMEDOWN,
+            VolumeMute | Mute => KEY_MUTE,
+            ChannelList => KEY_LIST,
+            ChannelListNext => KEY_CHANNELUP,
+            ChannelListPrev => KEY_CHANNELDOWN,
+            Tools => KEY_OPTION,
+            Info => KEY_INFO,
+            Return => KEY_BACK,
+            Exit => KEY_EXIT,
+            Enter => KEY_ENTER,
+            Up => KEY_UP,
+            Down => KEY_DOWN,
+            Left => KEY_LEFT,
+            Right => KEY_RIGHT,
+            Red => KEY_RED,
+            Green => KEY_GREEN,
+            Yellow => KEY_YELLOW,
+            Blue => KEY_BLUE,
+            Emanual => KEY_HELP,
+            PictureSize => KEY_ASPECT_RATIO,
+            Subtitle => KEY_SUBTITLE,
+            Stop => KEY_STOP,
+            Rewind => KEY_REWIND,
+            Play => KEY_PLAY,
+            Paus => KEY_PAUSE,
+            Play_Paus => KEY_PLAYPAUSE,
+            Forward => KEY_FASTFORWARD,
+            Mode => KEY_MODE,
+            Shuffle | Random => KEY_SHUFFLE,
+            Next => KEY_NEXTSONG,
+            Prev => KEY_PREVIOUSSONG,
+            Time => KEY_TIME,
+            Setup => KEY_SETUP,
+            Prog => KEY_PROGRAM,
+            _ => return None,
+        };
+
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KEYCODES;
+    use crate::Button;
+
+    /// Every button, in declaration order
+    const BUTTONS: &[Button] = &[
+        Button::Power,
+        Button::Source,
+        Button::One,
+        Button::Two,
+        Button::Three,
+        Button::Four,
+        Button::Five,
+        Button::Six,
+        Button::Seven,
+        Button::Eight,
+        Button::Nine,
+        Button::Zero,
+        Button::Teletext,
+        Button::ChannelPrev,
+        Button::VolumeUp,
+        Button::VolumeDown,
+        Button::VolumeMute,
+        Button::ChannelList,
+        Button::ChannelListNext,
+        Button::ChannelListPrev,
+        Button::Tools,
+        Button::Info,
+        Button::Return,
+        Button::Exit,
+        Button::Enter,
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+        Button::Red,
+        Button::Green,
+        Button::Yellow,
+        Button::Blue,
+        Button::Emanual,
+        Button::PictureSize,
+        Button::Subtitle,
+        Button::Stop,
+        Button::Rewind,
+        Button::Play,
+        Button::Paus,
+        Button::Play_Paus,
+        Button::Forward,
+        Button::Mode,
+        Button::Shuffle,
+        Button::U_SD,
+        Button::Plus,
+        Button::Minus,
+        Button::Next,
+        Button::Prev,
+        Button::Eq,
+        Button::Mute,
+        Button::Random,
+        Button::Repeat,
+        Button::Time,
+        Button::Setup,
+        Button::PitchReset,
+        Button::PitchPlus,
+        Button::PitchMinus,
+        Button::Prog,
+    ];
+
+    #[test]
+    fn all_buttons() {
+        for (i, button) in BUTTONS.iter().enumerate() {
+            assert_eq!(*button as usize, i, "{:?}", button);
+        }
+    }
+
+    /// Doesn't build when a button is added, as a reminder to add it to `BUTTONS` as well
+    #[allow(dead_code)]
+    fn every_button(button: Button) {
+        use Button::*;
+
+        match button {
+            Power | Source | One | Two | Three | Four | Five | Six | Seven | Eight | Nine
+            | Zero | Teletext | ChannelPrev | VolumeUp | VolumeDown | VolumeMute | ChannelList
+            | ChannelListNext | ChannelListPrev | Tools | Info | Return | Exit | Enter | Up
+            | Down | Left | Right | Red | Green | Yellow | Blue | Emanual | PictureSize
+            | Subtitle | Stop | Rewind | Play | Paus | Play_Paus | Forward | Mode | Shuffle
+            | U_SD | Plus | Minus | Next | Prev | Eq | Mute | Random | Repeat | Time | Setup
+            | PitchReset | PitchPlus | PitchMinus | Prog => (),
+        }
+    }
+
+    #[test]
+    fn registered_keycodes() {
+        // Every keycode a button maps to is registered by uinput
+        for button in BUTTONS {
+            if let Some(code) = button.linux_keycode() {
+                assert!(KEYCODES.contains(&code), "{:?}", button);
+            }
+        }
+
+        // And every registered keycode is used by a button, once
+        for (i, code) in KEYCODES.iter().enumerate() {
+            assert!(
+                BUTTONS.iter().any(|b| b.linux_keycode() == Some(*code)),
+                "{}",
+                code
+            );
+            assert!(!KEYCODES[i + 1..].contains(code), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! Linux LIRC character device support
+//!
+//! In `LIRC_MODE_MODE2` a `/dev/lircN` device delivers a stream of 32 bit samples, each with the
+//! sample type in the upper 8 bits and a duration in microseconds in the lower 24 bits. The
+//! samples are in the byte order of the host.
+//!
+//! In `LIRC_MODE_PULSE` the device transmits a buffer of alternating pulse and space
+//! durations in microseconds, starting and ending with a pulse.
+
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use crate::recv::{EventReceiver, ReceiverSM};
+use crate::sender::PulseSender;
+use crate::Carrier;
+
+pub const LIRC_MODE2_SPACE: u32 = 0x0000_0000;
+pub const LIRC_MODE2_PULSE: u32 = 0x0100_0000;
+pub const LIRC_MODE2_FREQUENCY: u32 = 0x0200_0000;
+pub const LIRC_MODE2_TIMEOUT: u32 = 0x0300_0000;
+pub const LIRC_MODE2_OVERFLOW: u32 = 0x0400_0000;
+
+pub const LIRC_VALUE_MASK: u32 = 0x00FF_FFFF;
+pub const LIRC_MODE2_MASK: u32 = 0xFF00_0000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Mode2 sample
+pub enum Mode2 {
+    /// Carrier present for the duration
+    Pulse(u32),
+    /// No carrier for the duration
+    Space(u32),
+    /// Measured carrier frequency in Hz
+    Frequency(u32),
+    /// No edges for the duration, the receiver is idle
+    Timeout(u32),
+    /// The receiver overflowed
+    Overflow(u32),
+}
+
+impl Mode2 {
+    /// Parse a raw sample
+    pub fn from_raw(sample: u32) -> Option<Self> {
+        let value = sample & LIRC_VALUE_MASK;
+
+        match sample & LIRC_MODE2_MASK {
+            LIRC_MODE2_SPACE => Some(Mode2::Space(value)),
+            LIRC_MODE2_PULSE => Some(Mode2::Pulse(value)),
+            LIRC_MODE2_FREQUENCY => Some(Mode2::Frequency(value)),
+            LIRC_MODE2_TIMEOUT => Some(Mode2::Timeout(value)),
+            LIRC_MODE2_OVERFLOW => Some(Mode2::Overflow(value)),
+            _ => None,
+        }
+    }
+
+    /// The sample as an edge event for a receiver state machine
+    ///
+    /// A pulse ends with the negative edge, and a space with the positive edge.
+    pub fn edge(&self) -> Option<(bool, u32)> {
+        match *self {
+            Mode2::Pulse(dt) => Some((false, dt)),
+            Mode2::Space(dt) => Some((true, dt)),
+            _ => None,
+        }
+    }
+}
+
+/// Reader of mode2 samples
+pub struct Mode2Reader<R> {
+    r: R,
+}
+
+impl<R: Read> Mode2Reader<R> {
+    /// Create a new reader for a device, or any other source of mode2 data, in native byte order
+    pub fn new(r: R) -> Self {
+        Self { r }
+    }
+
+    /// Hand back the reader
+    pub fn into_inner(self) -> R {
+        self.r
+    }
+
+    /// Read the next sample. Returns None at end of file.
+    pub fn read_sample(&mut self) -> io::Result<Option<Mode2>> {
+        let mut buf = [0u8; 4];
+
+        match self.r.read_exact(&mut buf) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        let sample = u32::from_ne_bytes(buf);
+
+        Mode2::from_raw(sample)
+            .map(Some)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid mode2 sample"))
+    }
+
+    /// Read samples into `recv` until a command is decoded, or the end of the file is reached
+    ///
+    /// `recv` should run at 1 MHz, mode2 durations are in microseconds. Decode errors are
+    /// skipped and timeouts and overflows reset the receiver.
+    pub fn receive<SM: ReceiverSM>(
+        &mut self,
+        recv: &mut EventReceiver<SM>,
+    ) -> io::Result<Option<SM::Cmd>> {
+        while let Some(sample) = self.read_sample()? {
+            match sample {
+                Mode2::Pulse(_) | Mode2::Space(_) => {
+                    let (edge, dt) = sample.edge().unwrap();
+
+                    if let Ok(Some(cmd)) = recv.edge_event(edge, dt) {
+                        return Ok(Some(cmd));
+                    }
+                }
+                Mode2::Timeout(_) | Mode2::Overflow(_) => recv.reset(),
+                Mode2::Frequency(_) => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<R: Read> Iterator for Mode2Reader<R> {
+    type Item = io::Result<Mode2>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_sample().transpose()
+    }
+}
+
+/// Most durations the kernel accepts in one transmission (`LIRCBUF_SIZE`)
+pub const LIRC_MAX_PULSES: usize = 1024;
+
+/// Writer able to modulate the pulses with a carrier, such as a LIRC device
+pub trait SetCarrier {
+    /// Set the carrier frequency and duty cycle
+    fn set_carrier(&mut self, carrier: Carrier) -> io::Result<()>;
+}
+
+/// Transmitter for a LIRC device in `LIRC_MODE_PULSE`
+pub struct LircTransmitter<W> {
+    w: W,
+    pulses: Vec<u32>,
+    /// Carrier last set on the device
+    carrier: Option<Carrier>,
+}
+
+impl<W: Write> LircTransmitter<W> {
+    pub fn new(w: W) -> Self {
+        Self {
+            w,
+            pulses: Vec::new(),
+            carrier: None,
+        }
+    }
+
+    /// Hand back the writer
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+
+    /// Transmit a buffer of pulse and space durations in microseconds. The buffer must start
+    /// and end with a pulse.
+    pub fn send_pulses(&mut self, pulses: &[u32]) -> io::Result<()> {
+        if pulses.len() & 0x1 == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "pulse buffer must start and end with a pulse",
+            ));
+        }
+
+        let bytes: Vec<u8> = pulses.iter().flat_map(|p| p.to_ne_bytes()).collect();
+
+        // The device transmits one buffer for each write
+        let n = self.w.write(&bytes)?;
+        if n != bytes.len() {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "short write"));
+        }
+        self.w.flush()
+    }
+}
+
+impl<W: Write + SetCarrier> LircTransmitter<W> {
+    /// Transmit `cmd`, encoded by `S`, with the carrier of its protocol
+    pub fn send<CMD, S: PulseSender<CMD>>(&mut self, cmd: &CMD) -> io::Result<()> {
+        self.use_carrier(S::carrier(cmd))?;
+
+        let mut pulses = core::mem::take(&mut self.pulses);
+        pulses.resize(LIRC_MAX_PULSES, 0);
+
+        let res = match S::pulses(cmd, &mut pulses) {
+            Some(n) => self.send_pulses(&pulses[..n]),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame doesn't fit in a LIRC transmission",
+            )),
+        };
+
+        self.pulses = pulses;
+        res
+    }
+
+    /// Set the carrier for the pulses sent after this, if it isn't already in use
+    pub fn use_carrier(&mut self, carrier: Carrier) -> io::Result<()> {
+        if self.carrier != Some(carrier) {
+            // Forget the old carrier, the device might be left half way between them
+            self.carrier = None;
+            self.w.set_carrier(carrier)?;
+            self.carrier = Some(carrier);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod device {
+    use std::fs::{File, OpenOptions};
+    use std::io;
+
+    use super::{LircTransmitter, SetCarrier};
+    use crate::linux::ioctl;
+    use crate::Carrier;
+
+    const LIRC_SET_SEND_MODE: u32 = 0x4004_6911;
+    const LIRC_SET_SEND_CARRIER: u32 = 0x4004_6913;
+    const LIRC_SET_SEND_DUTY_CYCLE: u32 = 0x4004_6915;
+    const LIRC_MODE_PULSE: u32 = 0x0000_0002;
+
+    impl LircTransmitter<File> {
+        /// Open the LIRC device at `path` (for example `/dev/lirc0`) in `LIRC_MODE_PULSE`
+        pub fn open(path: &str) -> io::Result<Self> {
+            let file = OpenOptions::new().write(true).open(path)?;
+            set(&file, LIRC_SET_SEND_MODE, LIRC_MODE_PULSE)?;
+            Ok(Self::new(file))
+        }
+    }
+
+    /// Sets the carrier of a LIRC device
+    impl SetCarrier for File {
+        fn set_carrier(&mut self, carrier: Carrier) -> io::Result<()> {
+            set(self, LIRC_SET_SEND_CARRIER, carrier.frequency)?;
+            set(self, LIRC_SET_SEND_DUTY_CYCLE, carrier.duty_cycle.into())
+        }
+    }
+
+    fn set(file: &File, request: u32, value: u32) -> io::Result<()> {
+        ioctl(file, request, &value as *const u32 as std::os::raw::c_ulong)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "nec")]
+mod tests {
+    use std::fs;
+    use std::io::{self, Cursor, Write};
+    use std::vec::Vec;
+
+    use super::{LircTransmitter, Mode2, Mode2Reader, SetCarrier};
+    use crate::protocols::nec::{NecCommand, NecStandard, NecTypeSender};
+    use crate::protocols::Nec;
+    use crate::recv::EventReceiver;
+    use crate::Carrier;
+
+    // Two Nec frames, recorded on a little endian host
+    const NEC_MODE2: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/linux/testdata/nec.mode2");
+
+    /// The recording in the byte order of this host, as a device would deliver it
+    fn nec_mode2() -> Cursor<Vec<u8>> {
+        let data: Vec<u8> = fs::read(NEC_MODE2)
+            .unwrap()
+            .chunks(4)
+            .flat_map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]).to_ne_bytes())
+            .collect();
+
+        Cursor::new(data)
+    }
+
+    #[test]
+    fn samples() {
+        let reader = Mode2Reader::new(nec_mode2());
+        let samples: std::vec::Vec<_> = reader.map(Result::unwrap).collect();
+
+        assert_eq!(samples.len(), 2 * 69);
+        assert_eq!(samples[0], Mode2::Space(41250));
+        assert_eq!(samples[1], Mode2::Pulse(9075));
+        assert_eq!(samples[68], Mode2::Timeout(20000));
+    }
+
+    #[test]
+    fn receive() {
+        let mut reader = Mode2Reader::new(nec_mode2());
+        let mut recv: EventReceiver<Nec> = EventReceiver::new(1_000_000);
+
+        for _ in 0..2 {
+            let cmd = reader.receive(&mut recv).unwrap().unwrap();
+            assert_eq!((cmd.addr, cmd.cmd), (0, 12));
+        }
+
+        assert!(reader.receive(&mut recv).unwrap().is_none());
+    }
+
+    /// Device recording the transmitted bytes and the carriers set
+    #[derive(Default)]
+    struct Device {
+        data: Vec<u8>,
+        carriers: Vec<Carrier>,
+    }
+
+    impl Write for Device {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.data.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SetCarrier for Device {
+        fn set_carrier(&mut self, carrier: Carrier) -> io::Result<()> {
+            self.carriers.push(carrier);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn transmit() {
+        let mut tx = LircTransmitter::new(Device::default());
+
+        let cmd = NecCommand::new(7, 44);
+        tx.send::<_, NecTypeSender<NecStandard>>(&cmd).unwrap();
+        tx.send::<_, NecTypeSender<NecStandard>>(&cmd).unwrap();
+
+        let device = tx.into_inner();
+        assert_eq!(device.carriers, [Carrier::new(38_000, 33)]);
+
+        let pulses: Vec<u32> = device
+            .data
+            .chunks(4)
+            .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+
+        // Header, 32 bits and the stop bit, twice
+        assert_eq!(pulses.len(), 2 * (2 + 64 + 1));
+        assert_eq!(&pulses[..4], &[9000, 4500, 560, 1690]);
+
+        // Receive what was sent
+        let mut recv: EventReceiver<Nec> = EventReceiver::new(1_000_000);
+        let mut cmd = None;
+        for (i, dt) in pulses[..67].iter().enumerate() {
+            cmd = cmd.or(recv.edge_event(i % 2 == 1, *dt).unwrap());
+        }
+        let cmd = cmd.unwrap();
+        assert_eq!((cmd.addr, cmd.cmd), (7, 44));
+    }
+}
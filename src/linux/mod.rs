@@ -0,0 +1,21 @@
+//! Linux host support
+
+pub mod keycodes;
+pub mod lirc;
+#[cfg(target_os = "linux")]
+pub mod uinput;
+
+#[cfg(target_os = "linux")]
+fn ioctl(file: &std::fs::File, request: u32, arg: std::os::raw::c_ulong) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // Safety: The requests used take an integer argument, a pointer to a value that outlives
+    // the call, or no argument at all
+    let res = unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg) };
+
+    if res < 0 {
+        Err(std::io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+//! Keyboard events to a uinput device
+//!
+//! The writer works with anything implementing `Write`, a regular file can stand in for
+//! `/dev/uinput` when testing.
+
+use std::io::{self, Write};
+use std::mem::size_of;
+
+use crate::{Button, ButtonEvent};
+
+/// Event types from `linux/input-event-codes.h`
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+/// Synchronization event
+pub const SYN_REPORT: u16 = 0;
+
+/// Key values
+pub const KEY_RELEASED: i32 = 0;
+pub const KEY_PRESSED: i32 = 1;
+pub const KEY_REPEATED: i32 = 2;
+
+/// Size of the name in `struct uinput_user_dev`
+const UINPUT_MAX_NAME_SIZE: usize = 80;
+/// Number of abs axes in `struct uinput_user_dev`
+const ABS_CNT: usize = 0x40;
+/// Bus type for virtual devices
+const BUS_VIRTUAL: u16 = 0x06;
+
+/// Writes keyboard events as `struct input_event`
+pub struct UinputWriter<W> {
+    w: W,
+}
+
+impl<W: Write> UinputWriter<W> {
+    pub fn new(w: W) -> Self {
+        Self { w }
+    }
+
+    /// Hand back the writer
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+
+    /// Write a key event with `value` (released, pressed or repeated) followed by a sync
+    pub fn key(&mut self, code: u16, value: i32) -> io::Result<()> {
+        self.write_event(EV_KEY, code, value)?;
+        self.write_event(EV_SYN, SYN_REPORT, 0)?;
+        self.w.flush()
+    }
+
+    /// Press and release the key for `button`
+    ///
+    /// Returns false if the button has no keycode
+    pub fn button(&mut self, button: Button) -> io::Result<bool> {
+        match button.linux_keycode() {
+            Some(code) => {
+                self.key(code, KEY_PRESSED)?;
+                self.key(code, KEY_RELEASED)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Write the key event for a button event. Long presses have no key event of their own,
+    /// the key is still repeated.
+    ///
+    /// Returns false if nothing was written
+    pub fn button_event(&mut self, event: ButtonEvent) -> io::Result<bool> {
+        let (button, value) = match event {
+            ButtonEvent::Pressed(b) => (b, KEY_PRESSED),
+            ButtonEvent::Repeated(b) => (b, KEY_REPEATED),
+            ButtonEvent::Released(b) => (b, KEY_RELEASED),
+            ButtonEvent::LongPress(_) => return Ok(false),
+        };
+
+        match button.linux_keycode() {
+            Some(code) => self.key(code, value).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Write a `struct uinput_user_dev` describing the device. This is how the device is set up
+    /// with the legacy uinput interface, before it's created.
+    pub fn write_user_dev(&mut self, name: &str) -> io::Result<()> {
+        let mut dev = [0u8; UINPUT_MAX_NAME_SIZE + 8 + 4 + 4 * ABS_CNT * 4];
+
+        let name = name.as_bytes();
+        let len = name.len().min(UINPUT_MAX_NAME_SIZE - 1);
+        dev[..len].copy_from_slice(&name[..len]);
+
+        // struct input_id, the vendor, product and version are left as 0
+        dev[UINPUT_MAX_NAME_SIZE..UINPUT_MAX_NAME_SIZE + 2]
+            .copy_from_slice(&BUS_VIRTUAL.to_ne_bytes());
+
+        self.w.write_all(&dev)
+    }
+
+    fn write_event(&mut self, kind: u16, code: u16, value: i32) -> io::Result<()> {
+        // struct input_event is the timestamp, set by the kernel, followed by the 8 bytes of
+        // type, code and value. The size of the timestamp depends on the target.
+        let time = [0u8; size_of::<libc::input_event>() - 8];
+
+        self.w.write_all(&time)?;
+        self.w.write_all(&kind.to_ne_bytes())?;
+        self.w.write_all(&code.to_ne_bytes())?;
+        self.w.write_all(&value.to_ne_bytes())
+    }
+}
+
+mod device {
+    use std::fs::{File, OpenOptions};
+    use std::io;
+
+    use super::{UinputWriter, EV_KEY};
+    use crate::linux::{ioctl, keycodes::KEYCODES};
+
+    const UI_DEV_CREATE: u32 = 0x5501;
+    const UI_DEV_DESTROY: u32 = 0x5502;
+    const UI_SET_EVBIT: u32 = 0x4004_5564;
+    const UI_SET_KEYBIT: u32 = 0x4004_5565;
+
+    impl UinputWriter<File> {
+        /// Create a keyboard device, with the keys buttons are mapped to, at `path`
+        /// (usually `/dev/uinput`)
+        pub fn create(path: &str, name: &str) -> io::Result<Self> {
+            let file = OpenOptions::new().write(true).open(path)?;
+
+            ioctl(&file, UI_SET_EVBIT, EV_KEY.into())?;
+            for code in KEYCODES {
+                ioctl(&file, UI_SET_KEYBIT, (*code).into())?;
+            }
+
+            let mut writer = UinputWriter::new(file);
+            writer.write_user_dev(name)?;
+            ioctl(&writer.w, UI_DEV_CREATE, 0)?;
+
+            Ok(writer)
+        }
+
+        /// Destroy the device
+        pub fn destroy(self) -> io::Result<()> {
+            ioctl(&self.w, UI_DEV_DESTROY, 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::mem::size_of;
+
+    use super::UinputWriter;
+    use crate::linux::keycodes::KEY_PLAY;
+    use crate::{Button, ButtonEvent};
+
+    #[test]
+    fn write_events() {
+        let path = std::env::temp_dir().join("infrared-uinput-test");
+        let file = File::create(&path).unwrap();
+
+        let mut writer = UinputWriter::new(file);
+        assert!(writer
+            .button_event(ButtonEvent::Pressed(Button::Play))
+            .unwrap());
+        assert!(!writer
+            .button_event(ButtonEvent::LongPress(Button::Play))
+            .unwrap());
+        assert!(writer
+            .button_event(ButtonEvent::Released(Button::Play))
+            .unwrap());
+        assert!(!writer.button(Button::Eq).unwrap());
+        drop(writer);
+
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let evsize = size_of::<libc::input_event>();
+        assert_eq!(data.len(), 4 * evsize);
+
+        let events: std::vec::Vec<(u16, u16, i32)> = data
+            .chunks(evsize)
+            .map(|ev| {
+                let ev = &ev[evsize - 8..];
+                (
+                    u16::from_ne_bytes([ev[0], ev[1]]),
+                    u16::from_ne_bytes([ev[2], ev[3]]),
+                    i32::from_ne_bytes([ev[4], ev[5], ev[6], ev[7]]),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            events,
+            [(1, KEY_PLAY, 1), (0, 0, 0), (1, KEY_PLAY, 0), (0, 0, 0)]
+        );
+    }
+}
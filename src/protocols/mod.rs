@@ -22,4 +22,5 @@ pub use rc6::Rc6;
 #[doc(inline)]
 pub use sbp::Sbp;
 
+#[cfg(any(feature = "nec", feature = "sbp"))]
 pub(crate) mod utils;
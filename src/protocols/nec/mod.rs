@@ -7,7 +7,7 @@ use crate::{cmd::Protocol, Command};
 pub mod receiver;
 pub mod send;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 #[doc(inline)]
 pub use receiver::Nec;
@@ -28,7 +28,9 @@ pub struct Nec16;
 pub struct NecCommand<VARIANT: NecVariant + ?Sized = NecStandard> {
     pub addr: u16,
     pub cmd: u8,
-    var: PhantomData<VARIANT>, //pub repeat: bool,
+    /// Received as a repeat frame
+    pub repeat: bool,
+    var: PhantomData<VARIANT>,
 }
 
 impl<V: NecVariant> NecCommand<V> {
@@ -36,6 +38,7 @@ impl<V: NecVariant> NecCommand<V> {
         NecCommand {
             addr,
             cmd,
+            repeat: false,
             var: PhantomData,
         }
     }
@@ -55,12 +58,17 @@ impl<VARIANT: NecVariant> Command for NecCommand<VARIANT> {
     }
 
     fn protocol(&self) -> Protocol {
-        Protocol::Nec
+        VARIANT::PROTOCOL
+    }
+
+    fn is_repeat(&self) -> bool {
+        self.repeat
     }
 }
 
 pub trait NecVariant {
     const TIMING: &'static NecTiming;
+    const PROTOCOL: Protocol;
 
     fn cmd_to_bits(cmd: &NecCommand<Self>) -> u32;
     fn cmd_from_bits(bits: u32) -> NecCommand<Self>;
@@ -69,6 +77,7 @@ pub trait NecVariant {
 
 impl NecVariant for NecStandard {
     const TIMING: &'static NecTiming = &STANDARD_TIMING;
+    const PROTOCOL: Protocol = Protocol::Nec;
 
     // Encode to bit
     fn cmd_to_bits(cmd: &NecCommand) -> u32 {
@@ -83,11 +92,7 @@ impl NecVariant for NecStandard {
     fn cmd_from_bits(bits: u32) -> NecCommand<NecStandard> {
         let addr = ((bits) & 0xFF) as u16;
         let cmd = ((bits >> 16) & 0xFF) as u8;
-        NecCommand {
-            addr,
-            cmd,
-            var: PhantomData,
-        }
+        NecCommand::new(addr, cmd)
     }
 
     fn cmd_is_valid(bits: u32) -> bool {
@@ -97,6 +102,7 @@ impl NecVariant for NecStandard {
 
 impl NecVariant for Nec16 {
     const TIMING: &'static NecTiming = &STANDARD_TIMING;
+    const PROTOCOL: Protocol = Protocol::Nec16;
 
     fn cmd_to_bits(cmd: &NecCommand<Self>) -> u32 {
         let addr = u32::from(cmd.addr);
@@ -107,11 +113,7 @@ impl NecVariant for Nec16 {
     fn cmd_from_bits(bits: u32) -> NecCommand<Nec16> {
         let addr = ((bits) & 0xFFFF) as u16;
         let cmd = ((bits >> 16) & 0xFF) as u8;
-        NecCommand {
-            addr,
-            cmd,
-            var: PhantomData,
-        }
+        NecCommand::new(addr, cmd)
     }
 
     fn cmd_is_valid(bits: u32) -> bool {
@@ -128,6 +130,7 @@ impl NecVariant for NecSamsung {
         dh: 560,
         ol: 1690,
     };
+    const PROTOCOL: Protocol = Protocol::NecSamsung;
 
     fn cmd_to_bits(cmd: &NecCommand<Self>) -> u32 {
         let addr = u32::from(cmd.addr) | u32::from(cmd.addr) << 8;
@@ -138,11 +141,7 @@ impl NecVariant for NecSamsung {
     fn cmd_from_bits(bits: u32) -> NecCommand<NecSamsung> {
         let addr = ((bits) & 0xFF) as u16;
         let cmd = ((bits >> 16) & 0xFF) as u8;
-        NecCommand {
-            addr,
-            cmd,
-            var: PhantomData,
-        }
+        NecCommand::new(addr, cmd)
     }
 
     fn cmd_is_valid(bits: u32) -> bool {
@@ -5,6 +5,7 @@ use crate::{
     recv::{Error, ReceiverSM, State},
 };
 use core::marker::PhantomData;
+use core::ops::Range;
 
 /// Generic type for Nec Receiver
 pub struct Nec<N = NecStandard> {
@@ -14,12 +15,17 @@ pub struct Nec<N = NecStandard> {
     pub bitbuf: u32,
     // Timing and tolerances
     ranges: PulseWidthRange<PulseWidth>,
+    // Header mark
+    header: Range<u32>,
     // Last command (used by repeat)
     lastcommand: u32,
     // The type of Nec
     nectype: PhantomData<N>,
 
     last_rising: u32,
+
+    #[cfg(feature = "diagnostics")]
+    diag: crate::Diagnostics,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -62,6 +68,15 @@ impl<VARIANT: NecVariant> Nec<VARIANT> {
         Self::with_timing(timing)
     }
 
+    /// Done if the received bits are a valid command
+    fn check(&self) -> InternalState {
+        if VARIANT::cmd_is_valid(self.bitbuf) {
+            InternalState::Done
+        } else {
+            InternalState::Err(Error::Checksum)
+        }
+    }
+
     fn with_timing(timing: &NecTiming) -> Self {
         let tols = tolerances(timing);
         let ranges = PulseWidthRange::new(&tols);
@@ -71,12 +86,32 @@ impl<VARIANT: NecVariant> Nec<VARIANT> {
             bitbuf: 0,
             lastcommand: 0,
             nectype: PhantomData,
+            #[cfg(feature = "diagnostics")]
+            diag: ranges.diagnostics(timing.dh),
             ranges,
+            header: header_mark(timing),
             last_rising: 0,
         }
     }
 }
 
+#[cfg(feature = "diagnostics")]
+impl<N> Nec<N> {
+    /// Add the symbol of `pw` micro seconds to the diagnostics
+    fn diagnose(&mut self, pw: u32) {
+        let class = self.ranges.class(pw);
+
+        match self.state {
+            // Sync or repeat starts a frame
+            InternalState::Init if matches!(class, Some(0..=1)) => self.diag.start(),
+            InternalState::Receiving(_) => {}
+            _ => return,
+        }
+
+        self.diag.symbol(class, pw);
+    }
+}
+
 impl<N: NecVariant> ReceiverSM for Nec<N> {
     type Cmd = NecCommand<N>;
     type InternalState = InternalState;
@@ -91,18 +126,24 @@ impl<N: NecVariant> ReceiverSM for Nec<N> {
         use PulseWidth::*;
 
         if rising {
-            let pulsewidth = self.ranges.pulsewidth(self.last_rising + dt);
+            let width = self.last_rising + dt;
+            let pulsewidth = self.ranges.pulsewidth(width);
+
+            #[cfg(feature = "diagnostics")]
+            self.diagnose(width);
 
             self.state = match (self.state, pulsewidth) {
                 (Init,  Sync)   => Receiving(0),
                 (Init,  Repeat) => RepeatDone,
+                // A header mark followed by a space that is neither the header nor the repeat
+                (Init,  _) if self.header.contains(&self.last_rising) => Err(Error::Header),
                 (Init,  _)      => Init,
 
-                (Receiving(31),     One)    => { self.bitbuf |= 1 << 31; Done }
-                (Receiving(31),     Zero)   => Done,
+                (Receiving(31),     One)    => { self.bitbuf |= 1 << 31; self.check() }
+                (Receiving(31),     Zero)   => self.check(),
                 (Receiving(bit),    One)    => { self.bitbuf |= 1 << bit; Receiving(bit + 1) }
                 (Receiving(bit),    Zero)   => Receiving(bit + 1),
-                (Receiving(_),      _)      => Err(Error::Data),
+                (Receiving(bit),    _)      => Err(Error::pulse_width(bit as u8, width, Self::MAX_GAP)),
 
                 (Done,          _)  => Done,
                 (RepeatDone,    _)  => RepeatDone,
@@ -119,36 +160,44 @@ impl<N: NecVariant> ReceiverSM for Nec<N> {
     }
 
     fn command(&self) -> Option<Self::Cmd> {
-        Some(N::cmd_from_bits(self.bitbuf))
+        match self.state {
+            // Repeat of the last command, if we have seen one
+            InternalState::RepeatDone if self.lastcommand == 0 => None,
+            InternalState::RepeatDone => {
+                let mut cmd = N::cmd_from_bits(self.lastcommand);
+                cmd.repeat = true;
+                Some(cmd)
+            }
+            _ => Some(N::cmd_from_bits(self.bitbuf)),
+        }
+    }
+
+    #[cfg(feature = "diagnostics")]
+    fn diagnostics(&self) -> Option<&crate::Diagnostics> {
+        Some(&self.diag)
     }
 
     fn reset(&mut self) {
+        // Only a complete command can be repeated
+        if let InternalState::Done = self.state {
+            self.lastcommand = self.bitbuf;
+        }
         self.state = InternalState::Init;
-        self.lastcommand = if self.bitbuf == 0 {
-            self.lastcommand
-        } else {
-            self.bitbuf
-        };
         self.bitbuf = 0;
         self.last_rising = 0;
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum PulseWidth {
     Sync = 0,
     Repeat = 1,
     Zero = 2,
     One = 3,
+    #[default]
     NotAPulseWidth = 4,
 }
 
-impl Default for PulseWidth {
-    fn default() -> Self {
-        PulseWidth::NotAPulseWidth
-    }
-}
-
 impl From<usize> for PulseWidth {
     fn from(v: usize) -> Self {
         match v {
@@ -161,6 +210,15 @@ impl From<usize> for PulseWidth {
     }
 }
 
+const fn header_mark(t: &NecTiming) -> Range<u32> {
+    let tol = t.hh / 10;
+
+    Range {
+        start: t.hh - tol,
+        end: t.hh + tol,
+    }
+}
+
 const fn tolerances(t: &NecTiming) -> [(u32, u32); 4] {
     [
         ((t.hh + t.hl), 5),
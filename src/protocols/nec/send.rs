@@ -1,7 +1,9 @@
 use crate::{
     protocols::nec::{NecCommand, NecTiming, NecVariant},
-    sender::{Sender, State},
+    sender::{PulseBuffer, PulseSender, Sender, State},
+    Carrier,
 };
+use core::convert::TryFrom;
 use core::marker::PhantomData;
 
 enum InternalState {
@@ -31,10 +33,16 @@ struct NSamples {
 }
 
 impl<N: NecVariant> NecTypeSender<N> {
+    /// Carrier of the variant
+    pub const CARRIER: Carrier = match N::PROTOCOL.carrier() {
+        Some(carrier) => carrier,
+        None => panic!("every Nec variant has a known carrier"),
+    };
+
     pub fn new(samplerate: u32) -> Self {
-        let period: u32 = (1 * 1000) / (samplerate / 1000);
+        let period: u32 = 1000 / (samplerate / 1000);
 
-        let samples = NSamples::new(period, &N::TIMING);
+        let samples = NSamples::new(period, N::TIMING);
         Self {
             state: InternalState::Idle,
             samples,
@@ -117,6 +125,35 @@ impl<N: NecVariant> Sender<NecCommand<N>> for NecTypeSender<N> {
         self.state = InternalState::Idle;
         self.last_ts = 0;
     }
+
+    fn carrier(&self) -> Carrier {
+        Self::CARRIER
+    }
+}
+
+impl<N: NecVariant> PulseSender<NecCommand<N>> for NecTypeSender<N> {
+    fn encode<T: TryFrom<u32>>(cmd: &NecCommand<N>, buf: &mut PulseBuffer<T>) {
+        let timing = N::TIMING;
+        let bits = N::cmd_to_bits(cmd);
+
+        buf.mark(timing.hh);
+        buf.space(timing.hl);
+
+        for bit in 0..32 {
+            buf.mark(timing.dh);
+            buf.space(if bits & (1 << bit) != 0 {
+                timing.ol
+            } else {
+                timing.zl
+            });
+        }
+
+        buf.mark(timing.dh);
+    }
+
+    fn carrier(_cmd: &NecCommand<N>) -> Carrier {
+        Self::CARRIER
+    }
 }
 
 #[cfg(feature = "embedded-hal")]
@@ -4,6 +4,16 @@ use crate::{
     BufferedReceiver,
 };
 
+/// Mark and space durations of a standard Nec frame with address 7 and command 44
+pub(crate) fn frame() -> std::vec::Vec<u32> {
+    use crate::protocols::nec::NecTypeSender;
+    use crate::sender::PulseSender;
+
+    let mut pulses = [0u32; 68];
+    let n = NecTypeSender::<NecStandard>::pulses(&NecCommand::new(7, 44), &mut pulses).unwrap();
+    pulses[..n].to_vec()
+}
+
 #[test]
 fn standard_nec() {
     use std::vec::Vec;
@@ -45,6 +55,9 @@ fn standard_nec() {
             assert_eq!(cmd.cmd, 12);
         }
     }
+
+    // The capture with glitches, decoded through a GlitchFilter
+    crate::filter::tests::filters_capture_glitches::<Nec>(&dists, 7, (0, 12));
 }
 
 #[test]
@@ -90,3 +103,310 @@ fn cmd_nec16() {
     let cmd2 = Nec16::cmd_from_bits(bits);
     assert_eq!(cmd, cmd2);
 }
+
+#[test]
+fn repeat() {
+    let mut recv: EventReceiver<Nec> = EventReceiver::new(1_000_000);
+
+    // Repeat before any command
+    assert_eq!(recv.edge_event(true, 0), Ok(None));
+    assert_eq!(recv.edge_event(false, 9000), Ok(None));
+    assert_eq!(recv.edge_event(true, 2250), Ok(None));
+
+    let bits = NecStandard::cmd_to_bits(&NecCommand::new(7, 44));
+
+    let mut edges = std::vec![(true, 40_000), (false, 9000), (true, 4500)];
+    for bit in 0..32 {
+        let low = if bits & (1 << bit) != 0 { 1690 } else { 560 };
+        edges.push((false, 560));
+        edges.push((true, low));
+    }
+
+    let mut cmds = edges
+        .iter()
+        .filter_map(|(edge, dt)| recv.edge_event(*edge, *dt).unwrap());
+    let cmd = cmds.next().unwrap();
+    assert_eq!((cmd.addr, cmd.cmd, cmd.repeat), (7, 44, false));
+
+    assert_eq!(recv.edge_event(false, 560), Ok(None));
+    assert_eq!(recv.edge_event(true, 40_000), Ok(None));
+    assert_eq!(recv.edge_event(false, 9000), Ok(None));
+    let cmd = recv.edge_event(true, 2250).unwrap().unwrap();
+    assert_eq!((cmd.addr, cmd.cmd, cmd.repeat), (7, 44, true));
+}
+
+#[test]
+fn pulses() {
+    use crate::protocols::nec::NecTypeSender;
+    use crate::sender::PulseSender;
+
+    let cmd = NecCommand::new(7, 44);
+    let mut buf = [0u32; 80];
+    let n = NecTypeSender::<NecStandard>::pulses(&cmd, &mut buf).unwrap();
+
+    assert_eq!(n, 67);
+    assert_eq!(&buf[..4], &[9000, 4500, 560, 1690]);
+
+    let mut recv: EventReceiver<Nec> = EventReceiver::new(1_000_000);
+    let mut received = None;
+    // Pulses end with the negative edge, spaces with the positive
+    for (i, dt) in buf[..n].iter().enumerate() {
+        received = received.or(recv.edge_event(i & 0x1 == 1, *dt).unwrap());
+    }
+    assert_eq!(received, Some(cmd));
+
+    // Timer ticks at 40 kHz
+    let mut ticks = [0u16; 67];
+    let n = NecTypeSender::<NecStandard>::pulses_ticks(&cmd, &mut ticks, 40_000).unwrap();
+    assert_eq!(&ticks[..4], &[360, 180, 22, 67]);
+    assert_eq!(n, 67);
+
+    // Too small buffer
+    let mut small = [0u16; 66];
+    assert_eq!(NecTypeSender::<NecStandard>::pulses(&cmd, &mut small), None);
+}
+
+#[test]
+#[cfg(feature = "embedded-hal")]
+fn software_carrier() {
+    use crate::protocols::nec::NecTypeSender;
+    use crate::sender::{CarrierSender, Sender, State};
+    use embedded_hal::digital::v2::OutputPin;
+
+    #[derive(Default)]
+    struct Pin {
+        high: bool,
+        rising: u32,
+    }
+
+    impl OutputPin for Pin {
+        type Error = ();
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.high = false;
+            Ok(())
+        }
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.rising += u32::from(!self.high);
+            self.high = true;
+            Ok(())
+        }
+    }
+
+    // Three ticks for each period of the 38 kHz carrier
+    let tickrate = 114_000;
+    let nec: NecTypeSender<NecStandard> = NecTypeSender::new(tickrate);
+    let mut sender = CarrierSender::new(nec, tickrate);
+    let mut pin = Pin::default();
+
+    sender.load(NecCommand::new(7, 44));
+
+    let mut on = 0;
+    let mut ts = 0;
+    // Header mark of 9 ms
+    while pin.rising < 300 {
+        if let State::Transmit(true) = sender.step_pin(ts, &mut pin).unwrap() {
+            on += 1;
+        }
+        ts += 1;
+    }
+
+    // One tick on for every three
+    assert_eq!(on, 300);
+    assert!((3 * 299..=3 * 300 + 1).contains(&ts));
+}
+
+#[test]
+#[cfg(feature = "embedded-hal")]
+fn software_carrier_wraps() {
+    use crate::protocols::nec::NecTypeSender;
+    use crate::sender::{CarrierSender, Sender, State};
+
+    let tickrate = 114_000;
+    let nec: NecTypeSender<NecStandard> = NecTypeSender::new(tickrate);
+    let mut sender = CarrierSender::new(nec, tickrate);
+
+    sender.load(NecCommand::new(7, 44));
+
+    // 2^32 isn't a multiple of the period, the carrier must not skip when the timestamp wraps
+    let start = u32::MAX - 30;
+    let mut levels = std::vec::Vec::new();
+    for i in 0..90 {
+        match sender.step(start.wrapping_add(i)) {
+            State::Transmit(on) => levels.push(on),
+            _ => panic!("not transmitting"),
+        }
+    }
+
+    let first = levels.iter().position(|on| *on).unwrap();
+    for (i, on) in levels[first..].iter().enumerate() {
+        assert_eq!(*on, i % 3 == 0, "tick {}", first + i);
+    }
+}
+
+#[test]
+fn nanoseconds() {
+    use crate::DurationReceiver;
+
+    let pulses = frame();
+
+    let mut recv: DurationReceiver<Nec> = DurationReceiver::new();
+    let mut cmd = recv.event_ns(true, 50_000_000_000).unwrap();
+    for (i, us) in pulses.iter().enumerate() {
+        let ns = u64::from(*us) * 1000 + 250;
+        cmd = cmd.or(recv.event_ns(i & 0x1 == 1, ns).unwrap());
+    }
+
+    let cmd = cmd.unwrap();
+    assert_eq!((cmd.addr, cmd.cmd), (7, 44));
+}
+
+#[test]
+fn idle_timeout() {
+    use crate::PeriodicReceiver;
+
+    let pulses = frame();
+
+    let mut recv: PeriodicReceiver<Nec> = PeriodicReceiver::new(1_000_000);
+    let mut ts = 100_000;
+
+    // Truncated frame
+    for (i, dt) in pulses[..20].iter().enumerate() {
+        assert!(recv.poll(i & 0x1 == 0, ts).unwrap().is_none());
+        ts += dt;
+    }
+    assert!(recv.poll(false, ts).unwrap().is_none());
+
+    // Idle for longer than the max gap, the truncated frame is dropped
+    assert!(recv.poll(false, ts + 11_000).unwrap().is_none());
+
+    // The next frame is received
+    ts += 50_000;
+    let mut cmd = None;
+    for (i, dt) in pulses.iter().enumerate() {
+        cmd = cmd.or(recv.poll(i & 0x1 == 0, ts).unwrap());
+        ts += dt;
+    }
+    cmd = cmd.or(recv.poll(false, ts).unwrap());
+
+    let cmd = cmd.unwrap();
+    assert_eq!((cmd.addr, cmd.cmd), (7, 44));
+}
+
+#[test]
+#[cfg(feature = "diagnostics")]
+fn diagnostics() {
+    use crate::recv::{Error, ReceiverSM};
+    use crate::DurationReceiver;
+
+    let pulses = frame();
+
+    // A remote running 4% slow
+    let slow: std::vec::Vec<u32> = pulses.iter().map(|us| us * 104 / 100).collect();
+
+    let mut recv: DurationReceiver<Nec> = DurationReceiver::new();
+    let mut cmd = None;
+    for (i, us) in slow.iter().enumerate() {
+        cmd = cmd.or(recv.event_us(i & 0x1 == 1, *us).unwrap());
+    }
+    assert!(cmd.is_some());
+
+    let diag = recv.sm.diagnostics().unwrap();
+    assert_eq!(diag.symbols, 33);
+    assert_eq!(diag.failed, None);
+    assert_eq!(diag.measured_unit(), 582);
+
+    let sync = diag.classes()[0];
+    assert_eq!(sync.count, 1);
+    assert_eq!(sync.max_deviation(), 540);
+    assert_eq!(sync.margin(), 134);
+    assert_eq!(diag.classes()[2].count + diag.classes()[3].count, 32);
+
+    // The space of bit 5 is too long
+    let mut bad = pulses;
+    bad[13] = 1000;
+
+    let err = bad
+        .iter()
+        .enumerate()
+        .find_map(|(i, us)| recv.event_us(i & 0x1 == 1, *us).err());
+    assert_eq!(
+        err,
+        Some(Error::PulseWidth {
+            bit: 5,
+            width: 1560
+        })
+    );
+
+    let diag = recv.sm.diagnostics().unwrap();
+    assert_eq!(diag.failed, Some(6));
+    assert_eq!(diag.symbols, 7);
+}
+
+#[test]
+#[cfg(feature = "stats")]
+fn statistics() {
+    use crate::{EventReceiver, Statistics};
+
+    let pulses = frame();
+
+    let repeat = [9000, 2250, 560];
+    let mut bad = pulses.clone();
+    bad[13] = 1000;
+
+    // A frame, a repeat, a bad frame and a truncated frame
+    let mut recv: EventReceiver<Nec> = EventReceiver::new(1_000_000);
+    let frames: [&[u32]; 4] = [&pulses[..], &repeat, &bad[..], &pulses[..20]];
+
+    for frame in frames {
+        let _ = recv.edge_event(true, 50_000);
+        for (i, dt) in frame.iter().enumerate() {
+            let _ = recv.edge_event(i & 0x1 == 1, *dt);
+        }
+        recv.timeout_check(20_000);
+    }
+
+    let stats = recv.stats();
+    assert_eq!((stats.frames, stats.repeats), (2, 1));
+    assert_eq!((stats.pulse_width_errors, stats.errors()), (1, 1));
+    assert_eq!(stats.timeouts, 1);
+
+    recv.reset_stats();
+    assert_eq!(recv.stats(), Statistics::default());
+}
+
+#[test]
+fn checksum() {
+    use crate::recv::Error;
+    use crate::DurationReceiver;
+
+    let mut pulses = frame();
+
+    // Flip the first bit of the inverted address
+    pulses[19] = if pulses[19] == 560 { 1690 } else { 560 };
+
+    let mut recv: DurationReceiver<Nec> = DurationReceiver::new();
+    let res: std::vec::Vec<_> = pulses
+        .iter()
+        .enumerate()
+        .map(|(i, us)| recv.event_us(i & 0x1 == 1, *us))
+        .filter(|res| res != &Ok(None))
+        .collect();
+
+    assert_eq!(res, [Err(Error::Checksum)]);
+}
+
+#[test]
+fn header() {
+    use crate::recv::Error;
+    use crate::DurationReceiver;
+
+    let mut recv: DurationReceiver<Nec> = DurationReceiver::new();
+
+    // Header mark followed by a space that is neither the header nor the repeat space
+    assert_eq!(recv.event_us(false, 9000), Ok(None));
+    assert_eq!(recv.event_us(true, 3000), Err(Error::Header));
+
+    // A mark that isn't a header mark is ignored while idle
+    assert_eq!(recv.event_us(false, 3000), Ok(None));
+    assert_eq!(recv.event_us(true, 3000), Ok(None));
+}
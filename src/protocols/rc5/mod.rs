@@ -8,6 +8,7 @@ pub use send::Rc5Sender;
 
 pub mod receiver;
 mod send;
+#[cfg(test)]
 mod tests;
 
 const ADDR_MASK: u16 = 0b_0000_0111_1100_0000;
@@ -78,4 +79,12 @@ impl Command for Rc5Command {
     fn protocol(&self) -> Protocol {
         Protocol::Rc5
     }
+
+    fn toggle(&self) -> Option<bool> {
+        Some(self.toggle != 0)
+    }
+
+    fn set_toggle(&mut self, toggle: bool) {
+        self.toggle = toggle.into();
+    }
 }
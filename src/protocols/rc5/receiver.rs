@@ -10,33 +10,43 @@ pub struct Rc5 {
     pub(crate) state: Rc5State,
     bitbuf: u16,
     pub(crate) rc5cntr: u32,
+    #[cfg(feature = "diagnostics")]
+    diag: Option<crate::Diagnostics>,
 }
 
 impl Rc5 {
     pub fn interval_to_units(&self, interval: u32) -> Option<u32> {
-        for i in 1..=2 {
-            if rc5_multiplier(i).contains(&interval) {
-                return Some(i);
-            }
+        (1..=2).find(|i| rc5_multiplier(*i).contains(&interval))
+    }
+
+    /// Add the interval of `dt` micro seconds to the diagnostics
+    #[cfg(feature = "diagnostics")]
+    fn diagnose(&mut self, rising: bool, dt: u32, units: Option<u32>) {
+        let diag = self.diag.get_or_insert_with(|| {
+            let classes = [1, 2].map(|n| {
+                let r = rc5_multiplier(n);
+                (889 * n, r.start, r.end - 1)
+            });
+            crate::Diagnostics::new(889, &classes)
+        });
+
+        match self.state {
+            Rc5State::Idle if rising => diag.start(),
+            Rc5State::Data(_) => diag.symbol(units.map(|n| n as usize - 1), dt),
+            _ => {}
         }
-        None
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum Rc5State {
+    #[default]
     Idle,
     Data(u8),
     Done,
     Err(Error),
 }
 
-impl Default for Rc5State {
-    fn default() -> Self {
-        Rc5State::Idle
-    }
-}
-
 impl From<Rc5State> for State {
     fn from(rs: Rc5State) -> Self {
         use Rc5State::*;
@@ -52,6 +62,8 @@ impl From<Rc5State> for State {
 impl ReceiverSM for Rc5 {
     type Cmd = Rc5Command;
     type InternalState = Rc5State;
+    // Longest gap in a frame is two half bits
+    const MAX_GAP: u32 = 5_000;
 
     fn create() -> Self {
         Rc5::default()
@@ -64,9 +76,14 @@ impl ReceiverSM for Rc5 {
         // Number of rc5 units since last pin edge
         let rc5units = self.interval_to_units(dt);
 
+        #[cfg(feature = "diagnostics")]
+        self.diagnose(rising, dt, rc5units);
+
         if let Some(units) = rc5units {
             self.rc5cntr += units;
-        } else {
+        } else if rising || dt > Self::MAX_GAP {
+            // A space that doesn't fit could be the gap before the next frame, start over from
+            // the edge after it. A mark that doesn't fit is a pulse width error.
             self.reset();
         }
 
@@ -87,7 +104,7 @@ impl ReceiverSM for Rc5 {
             (Data(bit), false,    Some(_)) if is_odd => Data(bit - 1),
 
             (Data(bit), _,          Some(_)) => Data(bit),
-            (Data(_),   _,          None) => Err(Error::Data),
+            (Data(bit), _,          None) => Err(Error::pulse_width(12 - bit, dt, Self::MAX_GAP)),
             (Done,      _,          _) => Done,
             (Err(err),  _,          _) => Err(err),
         };
@@ -99,6 +116,11 @@ impl ReceiverSM for Rc5 {
         Some(Rc5Command::from_bits(self.bitbuf))
     }
 
+    #[cfg(feature = "diagnostics")]
+    fn diagnostics(&self) -> Option<&crate::Diagnostics> {
+        self.diag.as_ref()
+    }
+
     fn reset(&mut self) {
         self.state = Rc5State::Idle;
         self.bitbuf = 0;
@@ -1,7 +1,12 @@
 use crate::{
     protocols::rc5::Rc5Command,
-    sender::{Sender, State},
+    sender::{PulseBuffer, PulseSender, Sender, State},
+    Carrier, Protocol,
 };
+use core::convert::TryFrom;
+
+/// Length of half a bit in us
+const HALFBIT: u32 = 889;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum InternalState {
@@ -18,11 +23,19 @@ pub struct Rc5Sender {
     cmd: Rc5Command,
     pub bits: u16,
     ts: u32,
+    /// Flip the toggle bit on each load
+    auto_toggle: bool,
+    toggle: bool,
 }
 
 impl Rc5Sender {
+    pub const CARRIER: Carrier = match Protocol::Rc5.carrier() {
+        Some(carrier) => carrier,
+        None => panic!("Rc5 has a known carrier"),
+    };
+
     pub fn new(samplerate: u32) -> Self {
-        let samples = (samplerate * 889) / 1_000_000;
+        let samples = (samplerate * HALFBIT) / 1_000_000;
 
         Self {
             state: InternalState::Disabled,
@@ -30,19 +43,42 @@ impl Rc5Sender {
             cmd: Rc5Command::from_bits(0),
             bits: 0,
             ts: 0,
+            auto_toggle: false,
+            toggle: false,
+        }
+    }
+
+    /// Sender that sets the toggle bit of the loaded commands, flipping it for each `load` and
+    /// keeping it for `repeat`
+    ///
+    /// Not for use with a `TransmitQueue`, which manages the toggle bit itself.
+    pub fn with_auto_toggle(samplerate: u32) -> Self {
+        Self {
+            auto_toggle: true,
+            ..Self::new(samplerate)
         }
     }
 
+    /// Send the last loaded command again, as a repeat of the same press
+    pub fn repeat(&mut self) {
+        self.state = InternalState::Idle;
+        self.bits = self.cmd.to_bits();
+    }
+
     pub fn baseunits_since_last(&self, ts: u32) -> bool {
         ts.wrapping_sub(self.ts) >= self.samples
     }
 }
 
 impl Sender<Rc5Command> for Rc5Sender {
-    fn load(&mut self, cmd: Rc5Command) {
-        self.state = InternalState::Idle;
+    fn load(&mut self, mut cmd: Rc5Command) {
+        if self.auto_toggle {
+            self.toggle = !self.toggle;
+            cmd.toggle = self.toggle.into();
+        }
+
         self.cmd = cmd;
-        self.bits = self.cmd.to_bits();
+        self.repeat();
     }
 
     fn step(&mut self, ts: u32) -> State {
@@ -86,6 +122,31 @@ impl Sender<Rc5Command> for Rc5Sender {
         self.bits = 0;
         self.ts = 0;
     }
+
+    fn carrier(&self) -> Carrier {
+        Self::CARRIER
+    }
+}
+
+impl PulseSender<Rc5Command> for Rc5Sender {
+    fn encode<T: TryFrom<u32>>(cmd: &Rc5Command, buf: &mut PulseBuffer<T>) {
+        let bits = cmd.to_bits();
+
+        // Manchester coded, a one is sent as space then mark
+        for bit in (0..14).rev() {
+            if bits & (1 << bit) != 0 {
+                buf.space(HALFBIT);
+                buf.mark(HALFBIT);
+            } else {
+                buf.mark(HALFBIT);
+                buf.space(HALFBIT);
+            }
+        }
+    }
+
+    fn carrier(_cmd: &Rc5Command) -> Carrier {
+        Self::CARRIER
+    }
 }
 
 #[cfg(feature = "embedded-hal")]
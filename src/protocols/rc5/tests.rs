@@ -1,135 +1,232 @@
-#[cfg(test)]
-mod tests {
-    use crate::protocols::rc5::{Rc5, Rc5Command};
-    use crate::recv::*;
-
-    #[test]
-    fn rc5_command() {
-        let cmd = Rc5Command::new(20, 15, false);
-        assert_eq!(cmd, Rc5Command::from_bits(cmd.to_bits()))
-    }
+use crate::protocols::rc5::{Rc5, Rc5Command, Rc5Sender};
+use crate::recv::*;
+use crate::sender::PulseSender;
+
+#[test]
+fn rc5_command() {
+    let cmd = Rc5Command::new(20, 15, false);
+    assert_eq!(cmd, Rc5Command::from_bits(cmd.to_bits()))
+}
 
-    #[test]
-    fn test_bufrecv() {
-        let dists = [
-            0, 37, 34, 72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37, 0, 37,
-            34, 72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37,
-        ];
+#[test]
+fn test_bufrecv() {
+    let dists = [
+        0, 37, 34, 72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37, 0, 37, 34,
+        72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37,
+    ];
 
-        let r: BufferedReceiver<Rc5> = BufferedReceiver::new(&dists, 40_000);
+    let r: BufferedReceiver<Rc5> = BufferedReceiver::new(&dists, 40_000);
 
-        for c in r {
-            println!("c = {:?}", c);
-            assert_eq!(c.addr, 20);
-            assert_eq!(c.cmd, 9);
-        }
+    for c in r {
+        println!("c = {:?}", c);
+        assert_eq!(c.addr, 20);
+        assert_eq!(c.cmd, 9);
     }
 
-    #[test]
-    fn command() {
-        let dists = [
-            0, 37, 34, 72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37, 0, 37,
-            34, 72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37,
-        ];
+    // The capture with glitches, decoded through a GlitchFilter
+    crate::filter::tests::filters_capture_glitches::<Rc5>(&dists, 5, (20, 9));
+}
 
-        let mut recv: EventReceiver<Rc5> = EventReceiver::new(40_000);
-        let mut edge = false;
-        let mut tot = 0;
+#[test]
+fn command() {
+    let dists = [
+        0, 37, 34, 72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37, 0, 37, 34,
+        72, 72, 73, 70, 72, 36, 37, 34, 36, 36, 36, 71, 73, 35, 37, 70, 37,
+    ];
 
-        for dist in dists.iter() {
-            edge = !edge;
-            tot += *dist;
+    let mut recv: EventReceiver<Rc5> = EventReceiver::new(40_000);
+    let mut edge = false;
+    let mut tot = 0;
 
-            let s0 = recv.sm.state;
-            let cmd = recv.edge_event(edge, tot);
-            let s1 = recv.sm.state;
+    for dist in dists.iter() {
+        edge = !edge;
+        tot += *dist;
 
-            println!("{} ({}): {:?} -> {:?}", edge as u32, dist, s0, s1,);
+        let s0 = recv.sm.state;
+        let cmd = recv.edge_event(edge, tot);
+        let s1 = recv.sm.state;
 
-            if let Ok(Some(cmd)) = cmd {
-                println!("cmd: {:?}", cmd);
-                assert_eq!(cmd.addr, 20);
-                assert_eq!(cmd.cmd, 9);
-            }
+        println!("{} ({}): {:?} -> {:?}", edge as u32, dist, s0, s1,);
+
+        if let Ok(Some(cmd)) = cmd {
+            println!("cmd: {:?}", cmd);
+            assert_eq!(cmd.addr, 20);
+            assert_eq!(cmd.cmd, 9);
         }
     }
+}
 
-    #[test]
-    #[rustfmt::skip]
-    fn command_mixed() {
-        let dists = [
-            57910, 36, 36, 36, 35, 37, 35, 72, 71, 72, 36, 36, 36, 36, 35, 36, 36, 36, 35, 36, 36, 36, 71, 36,
-            26605, 36, 36, 71, 72, 72, 71, 72, 36, 36, 35, 36, 36, 36, 35, 37, 35, 36, 36, 36, 71, 36,
-            // From another rc5 like protocol but not standard rc5, should be ignored by the receiver
-            10254, 37, 35, 37, 34, 37, 35, 37, 35, 73, 34, 38, 70, 37, 141, 38, 34, 37, 35, 37, 34, 38, 34, 37, 70, 73, 35, 37, 35, 37, 34, 38, 34, 37, 34, 38,
-            50973, 38, 34, 73, 70, 73, 70, 74, 34, 37, 35, 37, 34, 38, 34, 37, 35, 37, 34, 38, 70, 37,
-        ];
-
-        let mut recv: EventReceiver<Rc5> = EventReceiver::new(40_000);
-        let mut edge = false;
-        let mut tot = 0;
-
-        for dist in dists.iter() {
-            edge = !edge;
-            tot += *dist;
-
-            let s0 = recv.sm.state;
-            let cnt = recv.sm.rc5cntr;
-            let cmd = recv.edge_event(edge, tot);
-            let s1 = recv.sm.state;
-
-            println!(
-                "{} {} ({}): {:?} -> {:?}",
-                edge as u32,
-                cnt,
-                dist,
-                s0,
-                s1,
-            );
-
-            if let Ok(Some(cmd)) = cmd {
-                println!("cmd: {:?}", cmd);
-                assert_eq!(cmd.addr, 20);
-                assert_eq!(cmd.cmd, 1);
-            }
+#[test]
+#[rustfmt::skip]
+fn command_mixed() {
+    let dists = [
+        57910, 36, 36, 36, 35, 37, 35, 72, 71, 72, 36, 36, 36, 36, 35, 36, 36, 36, 35, 36, 36, 36, 71, 36,
+        26605, 36, 36, 71, 72, 72, 71, 72, 36, 36, 35, 36, 36, 36, 35, 37, 35, 36, 36, 36, 71, 36,
+        // From another rc5 like protocol but not standard rc5, should be ignored by the receiver
+        10254, 37, 35, 37, 34, 37, 35, 37, 35, 73, 34, 38, 70, 37, 141, 38, 34, 37, 35, 37, 34, 38, 34, 37, 70, 73, 35, 37, 35, 37, 34, 38, 34, 37, 34, 38,
+        50973, 38, 34, 73, 70, 73, 70, 74, 34, 37, 35, 37, 34, 38, 34, 37, 35, 37, 34, 38, 70, 37,
+    ];
+
+    let mut recv: EventReceiver<Rc5> = EventReceiver::new(40_000);
+    let mut edge = false;
+    let mut tot = 0;
+
+    for dist in dists.iter() {
+        edge = !edge;
+        tot += *dist;
+
+        let s0 = recv.sm.state;
+        let cnt = recv.sm.rc5cntr;
+        let cmd = recv.edge_event(edge, tot);
+        let s1 = recv.sm.state;
+
+        println!(
+            "{} {} ({}): {:?} -> {:?}",
+            edge as u32,
+            cnt,
+            dist,
+            s0,
+            s1,
+        );
+
+        if let Ok(Some(cmd)) = cmd {
+            println!("cmd: {:?}", cmd);
+            assert_eq!(cmd.addr, 20);
+            assert_eq!(cmd.cmd, 1);
         }
+    }
 
-        let r: BufferedReceiver<Rc5> = BufferedReceiver::new(&dists, 40_000);
+    let r: BufferedReceiver<Rc5> = BufferedReceiver::new(&dists, 40_000);
+    let mut received = 0;
 
-        for c in r {
-            println!("c = {:?}", c);
-            assert_eq!(c.addr, 20);
-            assert_eq!(c.cmd, 1);
-        }
+    for c in r {
+        println!("c = {:?}", c);
+        assert_eq!(c.addr, 20);
+        assert_eq!(c.cmd, 1);
+        received += 1;
+    }
+
+    // The receiver resyncs after the other protocol
+    assert_eq!(received, 3);
+}
 
+#[test]
+fn pulses() {
+    let cmd = Rc5Command::new(20, 9, false);
+    let mut buf = [0u32; 28];
+    let n = Rc5Sender::pulses(&cmd, &mut buf).unwrap();
+
+    // Start bits 1 1, toggle 0, address 10100, command 001001
+    assert_eq!(
+        &buf[..n],
+        &[
+            889, 889, 1778, 1778, 1778, 1778, 1778, 889, 889, 889, 889, 889, 889, 1778, 1778, 889,
+            889, 1778, 889
+        ]
+    );
+
+    let mut recv: EventReceiver<Rc5> = EventReceiver::new(1_000_000);
+    // Idle before the first pulse
+    let mut received = recv.edge_event(true, 0).unwrap();
+    for (i, dt) in buf[..n].iter().enumerate() {
+        received = received.or(recv.edge_event(i & 0x1 == 1, *dt).unwrap());
     }
+    assert_eq!(received, Some(cmd));
+}
+
+#[test]
+fn pulse_width() {
+    let cmd = Rc5Command::new(20, 9, false);
+    let mut buf = [0u32; 28];
+    let n = Rc5Sender::pulses(&cmd, &mut buf).unwrap();
+
+    // The mark of the toggle and first address bit is too long
+    let mut bad = buf;
+    bad[2] = 2400;
+
+    let mut recv: EventReceiver<Rc5> = EventReceiver::new(1_000_000);
+    recv.edge_event(true, 0).unwrap();
+    let err = bad[..n]
+        .iter()
+        .enumerate()
+        .find_map(|(i, dt)| recv.edge_event(i & 0x1 == 1, *dt).err());
+    assert_eq!(
+        err,
+        Some(Error::PulseWidth {
+            bit: 1,
+            width: 2400
+        })
+    );
+
+    // The frame after it is still received
+    let mut capture = std::vec![20_000];
+    capture.extend_from_slice(&bad[..n]);
+    capture.push(20_000);
+    capture.extend_from_slice(&buf[..n]);
+
+    let cmds: std::vec::Vec<_> = BufferedReceiver::<Rc5>::new(&capture, 1_000_000).collect();
+    assert_eq!(cmds, [cmd]);
+}
+
+#[test]
+fn auto_toggle() {
+    use crate::sender::{Sender, State};
+
+    fn send(tx: &mut Rc5Sender) -> Rc5Command {
+        let mut recv: EventReceiver<Rc5> = EventReceiver::new(1_000_000);
+        let mut received = recv.edge_event(true, 0).unwrap();
+        let (mut level, mut last) = (false, 0);
+
+        for ts in 0..30_000 {
+            let on = matches!(tx.step(ts), State::Transmit(true));
+            if on != level {
+                received = received.or(recv.edge_event(on, ts - last).unwrap());
+                level = on;
+                last = ts;
+            }
+        }
+        // Trailing edge when the command ends with a mark
+        received
+            .or(recv.edge_event(!level, 30_000 - last).unwrap())
+            .unwrap()
+    }
+
+    let mut tx = Rc5Sender::with_auto_toggle(1_000_000);
+    let play = Rc5Command::new(20, 53, false);
+
+    tx.load(play);
+    assert_eq!(send(&mut tx).toggle, 1);
+    tx.repeat();
+    assert_eq!(send(&mut tx).toggle, 1);
+    tx.load(play);
+    assert_eq!(send(&mut tx).toggle, 0);
+}
 
-    /*
-    #[test]
-    fn rc5_transmit() {
-        use crate::send::{State, Sender};
-        let mut tx = Rc5Sender::new(40_000);
+/*
+#[test]
+fn rc5_transmit() {
+    use crate::send::{State, Sender};
+    let mut tx = Rc5Sender::new(40_000);
 
-        tx.load(Rc5Command::new(20, 9, false));
+    tx.load(Rc5Command::new(20, 9, false));
 
-        println!("bits: {:X?}", tx.bits);
+    println!("bits: {:X?}", tx.bits);
 
-        let mut last_enable = false;
-        let mut last_ts = 0;
+    let mut last_enable = false;
+    let mut last_ts = 0;
 
-        for ts in 0..2000 {
-            let state = tx.step(ts);
+    for ts in 0..2000 {
+        let state = tx.step(ts);
 
-            if let State::Transmit(v) = state {
-                if v != last_enable {
-                    last_enable = v;
-                    let delta = ts - last_ts;
-                    println!("state: {}: {:?}", delta, state);
-                    last_ts = ts;
-                }
+        if let State::Transmit(v) = state {
+            if v != last_enable {
+                last_enable = v;
+                let delta = ts - last_ts;
+                println!("state: {}: {:?}", delta, state);
+                last_ts = ts;
             }
         }
     }
-
-     */
 }
+
+ */
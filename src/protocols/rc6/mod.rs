@@ -9,7 +9,10 @@ use crate::{
     Command,
 };
 
-#[derive(Debug)]
+mod send;
+pub use send::Rc6Sender;
+
+#[derive(Debug, Copy, Clone)]
 pub struct Rc6Cmd {
     pub addr: u8,
     pub cmd: u8,
@@ -48,6 +51,14 @@ impl Command for Rc6Cmd {
     fn protocol(&self) -> Protocol {
         Protocol::Rc6
     }
+
+    fn toggle(&self) -> Option<bool> {
+        Some(self.toggle)
+    }
+
+    fn set_toggle(&mut self, toggle: bool) {
+        self.toggle = toggle;
+    }
 }
 
 #[derive(Default)]
@@ -57,23 +68,39 @@ pub struct Rc6 {
     headerdata: u32,
     toggle: bool,
     rc6_counter: u32,
+    #[cfg(feature = "diagnostics")]
+    diag: Option<crate::Diagnostics>,
 }
 
 impl Rc6 {
     fn interval_to_units(&self, interval: u16) -> Option<u32> {
         let interval = u32::from(interval);
 
-        for i in 1..=6 {
-            if rc6_multiplier(i).contains(&interval) {
-                return Some(i);
-            }
+        (1..=6).find(|i| rc6_multiplier(*i).contains(&interval))
+    }
+
+    /// Add the interval of `dt` micro seconds to the diagnostics
+    #[cfg(feature = "diagnostics")]
+    fn diagnose(&mut self, rising: bool, dt: u32, units: Option<u32>) {
+        let diag = self.diag.get_or_insert_with(|| {
+            let classes = [1, 2, 3, 4, 5, 6].map(|n| {
+                let r = rc6_multiplier(n);
+                (444 * n, r.start, r.end - 1)
+            });
+            crate::Diagnostics::new(444, &classes)
+        });
+
+        match self.state {
+            Rc6State::Idle if rising => diag.start(),
+            Rc6State::Idle | Rc6State::Done | Rc6State::Rc6Err(_) => {}
+            _ => diag.symbol(units.map(|n| n as usize - 1), dt),
         }
-        None
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum Rc6State {
+    #[default]
     Idle,
     Leading,
     LeadingPaus,
@@ -84,12 +111,6 @@ pub enum Rc6State {
     Rc6Err(Error),
 }
 
-impl Default for Rc6State {
-    fn default() -> Self {
-        Rc6State::Idle
-    }
-}
-
 impl From<Rc6State> for State {
     fn from(state: Rc6State) -> Self {
         use Rc6State::*;
@@ -108,6 +129,8 @@ const FALLING: bool = false;
 impl ReceiverSM for Rc6 {
     type Cmd = Rc6Cmd;
     type InternalState = Rc6State;
+    // Longest gap in a frame is the leader mark of six units
+    const MAX_GAP: u32 = 5_000;
 
     fn create() -> Self {
         Self::default()
@@ -120,9 +143,14 @@ impl ReceiverSM for Rc6 {
         // Number of rc6 units since last pin edge
         let n_units = self.interval_to_units(dt as u16);
 
+        #[cfg(feature = "diagnostics")]
+        self.diagnose(rising, dt, n_units);
+
         if let Some(units) = n_units {
             self.rc6_counter += units;
-        } else {
+        } else if rising || dt > Self::MAX_GAP {
+            // A space that doesn't fit could be the gap before the next frame, start over from
+            // the edge after it. A mark that doesn't fit is a pulse width error.
             self.reset();
         }
 
@@ -132,9 +160,12 @@ impl ReceiverSM for Rc6 {
             (Idle,          FALLING,    _)          => Idle,
             (Idle,          RISING,     _)          => { self.rc6_counter = 0; Leading },
             (Leading,       FALLING,    Some(6))    => LeadingPaus,
-            (Leading,       _,          _)          => Idle,
+            (Leading,       FALLING,    Some(_))    => Rc6Err(Error::Header),
+            (Leading,       RISING,     _)          => Rc6Err(Error::UnexpectedEdge { bit: 0 }),
             (LeadingPaus,   RISING,     Some(2))    => HeaderData(3),
-            (LeadingPaus,   _,          _)          => Idle,
+            (LeadingPaus,   RISING,     Some(_))    => Rc6Err(Error::Header),
+            (LeadingPaus,   FALLING,    _)          => Rc6Err(Error::UnexpectedEdge { bit: 0 }),
+            (Leading | LeadingPaus, _,  None)       => Rc6Err(Error::pulse_width(0, dt, Self::MAX_GAP)),
 
             (HeaderData(n), _,          Some(_)) if odd => {
                 self.headerdata |= if rising { 0 } else { 1 } << n;
@@ -146,12 +177,12 @@ impl ReceiverSM for Rc6 {
             }
 
             (HeaderData(n), _,          Some(_))    => HeaderData(n),
-            (HeaderData(_), _,          None)       => Idle,
+            (HeaderData(n), _,          None)       => Rc6Err(Error::pulse_width(3 - n as u8, dt, Self::MAX_GAP)),
 
-            (Trailing,      FALLING,    Some(3))    => { self.toggle = false; Data(15) }
-            (Trailing,      RISING,     Some(2))    => { self.toggle = true; Data(15) }
+            (Trailing,      FALLING,    Some(3))    => { self.toggle = true; Data(15) }
+            (Trailing,      RISING,     Some(2))    => { self.toggle = false; Data(15) }
             (Trailing,      FALLING,    Some(1))    => Trailing,
-            (Trailing,      _,          _)          => Idle,
+            (Trailing,      _,          _)          => Rc6Err(Error::pulse_width(4, dt, Self::MAX_GAP)),
 
             (Data(0),       RISING,     Some(_)) if odd => Done,
             (Data(0),       FALLING,    Some(_)) if odd => { self.data |= 1; Done }
@@ -159,7 +190,7 @@ impl ReceiverSM for Rc6 {
             (Data(n),       RISING,     Some(_)) if odd => Data(n - 1),
             (Data(n),       FALLING,    Some(_)) if odd => { self.data |= 1 << n; Data(n - 1) }
             (Data(n),       _,          Some(_))    => Data(n),
-            (Data(_),       _,          None)       => Rc6Err(Error::Data),
+            (Data(n),       _,          None)       => Rc6Err(Error::pulse_width(20 - n as u8, dt, Self::MAX_GAP)),
 
             (Done,          _,          _)          => Done,
             (Rc6Err(err),    _,          _)         => Rc6Err(err),
@@ -172,6 +203,11 @@ impl ReceiverSM for Rc6 {
         Some(Rc6Cmd::from_bits(self.data, self.toggle))
     }
 
+    #[cfg(feature = "diagnostics")]
+    fn diagnostics(&self) -> Option<&crate::Diagnostics> {
+        self.diag.as_ref()
+    }
+
     fn reset(&mut self) {
         self.state = Rc6State::Idle;
         self.data = 0;
@@ -196,7 +232,7 @@ const fn range(len: u32, percent: u32) -> Range<u32> {
 
 #[cfg(test)]
 mod tests {
-    use crate::protocols::rc6::Rc6;
+    use crate::protocols::rc6::{Rc6, Rc6State};
     use crate::recv::*;
 
     #[test]
@@ -231,5 +267,116 @@ mod tests {
                 assert_eq!(cmd.cmd, 2);
             }
         }
+
+        // The capture with glitches, decoded through a GlitchFilter
+        crate::filter::tests::filters_capture_glitches::<Rc6>(&dists, 5, (70, 2));
+    }
+
+    #[test]
+    fn toggle() {
+        // A captured frame with the toggle bit cleared, the trailer bit is sent as a double
+        // length space then mark (34, 36)
+        let cleared = [
+            0, 108, 34, 19, 34, 19, 16, 20, 16, 19, 34, 36, 16, 37, 34, 20, 16, 19, 16, 37, 17, 19,
+            34, 19, 17, 19, 16, 19, 17, 19, 16, 20, 16, 19, 16, 37, 34, 20,
+        ];
+        // Setting it swaps the two, merging the mark with the mark before it and the space
+        // with the first half of the first data bit
+        let set = [
+            0, 108, 34, 19, 34, 19, 16, 20, 16, 55, 52, 37, 34, 20, 16, 19, 16, 37, 17, 19, 34, 19,
+            17, 19, 16, 19, 17, 19, 16, 20, 16, 19, 16, 37, 34, 20,
+        ];
+
+        for (dists, toggle) in [(&cleared[..], false), (&set[..], true)] {
+            let mut brecv: BufferedReceiver<Rc6> = BufferedReceiver::new(dists, 40_000);
+            let cmd = brecv.next().unwrap();
+
+            assert_eq!((cmd.addr, cmd.cmd, cmd.toggle), (70, 2, toggle));
+        }
+    }
+
+    #[test]
+    fn header_errors() {
+        use crate::DurationReceiver;
+
+        let mut recv: DurationReceiver<Rc6> = DurationReceiver::new();
+
+        // Leader mark of three units instead of six
+        assert!(matches!(recv.event_us(true, 10_000), Ok(None)));
+        assert_eq!(recv.event_us(false, 1332).err(), Some(Error::Header));
+
+        // A width that isn't a number of units
+        assert!(matches!(recv.event_us(true, 10_000), Ok(None)));
+        assert_eq!(
+            recv.event_us(false, 1100).err(),
+            Some(Error::PulseWidth {
+                bit: 0,
+                width: 1100
+            })
+        );
+        assert!(matches!(recv.sm.state, Rc6State::Idle));
+    }
+
+    #[test]
+    fn pulse_width() {
+        use crate::protocols::rc6::{Rc6Cmd, Rc6Sender};
+        use crate::sender::PulseSender;
+        use crate::DurationReceiver;
+
+        let mut pulses = [0u32; 48];
+        let n = Rc6Sender::pulses(&Rc6Cmd::new(70, 2), &mut pulses).unwrap();
+
+        // The mark of the second to last data bit is too long
+        pulses[n - 3] = 1050;
+
+        let mut recv: DurationReceiver<Rc6> = DurationReceiver::new();
+        recv.event_us(true, 10_000).unwrap();
+        let err = pulses[..n]
+            .iter()
+            .enumerate()
+            .find_map(|(i, us)| recv.event_us(i & 0x1 == 1, *us).err());
+
+        assert_eq!(
+            err,
+            Some(Error::PulseWidth {
+                bit: 19,
+                width: 1050
+            })
+        );
+    }
+
+    #[test]
+    fn auto_toggle() {
+        use crate::protocols::rc6::{Rc6Cmd, Rc6Sender};
+        use crate::sender::{Sender, State};
+
+        fn send(tx: &mut Rc6Sender) -> Rc6Cmd {
+            let mut recv: EventReceiver<Rc6> = EventReceiver::new(1_000_000);
+            let mut received = None;
+            let (mut level, mut last) = (false, 0);
+
+            for ts in 0..40_000 {
+                let on = matches!(tx.step(ts), State::Transmit(true));
+                if on != level {
+                    received = received.or(recv.edge_event(on, ts - last).unwrap());
+                    level = on;
+                    last = ts;
+                }
+            }
+            // Trailing edge when the command ends with a mark
+            received
+                .or(recv.edge_event(!level, 40_000 - last).unwrap())
+                .unwrap()
+        }
+
+        let mut tx = Rc6Sender::with_auto_toggle(1_000_000);
+        let cmd = Rc6Cmd::new(70, 2);
+
+        tx.load(cmd);
+        assert!(send(&mut tx).toggle);
+        tx.repeat();
+        assert!(send(&mut tx).toggle);
+        tx.load(cmd);
+        assert!(!send(&mut tx).toggle);
     }
 }
@@ -0,0 +1,120 @@
+use crate::{
+    protocols::rc6::Rc6Cmd,
+    sender::{FrameStepper, PulseBuffer, PulseSender, Sender, State},
+    Carrier, Protocol,
+};
+use core::convert::TryFrom;
+
+/// Length of one rc6 unit in us
+const UNIT: u32 = 444;
+
+/// Max number of mark and space durations in a frame
+const PULSES: usize = 44;
+
+pub struct Rc6Sender {
+    frame: FrameStepper<PULSES>,
+    /// Last loaded command
+    cmd: Option<Rc6Cmd>,
+    /// Flip the toggle bit on each load
+    auto_toggle: bool,
+    toggle: bool,
+}
+
+impl Rc6Sender {
+    pub const CARRIER: Carrier = match Protocol::Rc6.carrier() {
+        Some(carrier) => carrier,
+        None => panic!("Rc6 has a known carrier"),
+    };
+
+    pub fn new(samplerate: u32) -> Self {
+        Self {
+            frame: FrameStepper::new(samplerate),
+            cmd: None,
+            auto_toggle: false,
+            toggle: false,
+        }
+    }
+
+    /// Sender that sets the toggle bit of the loaded commands, flipping it for each `load` and
+    /// keeping it for `repeat`
+    ///
+    /// Not for use with a `TransmitQueue`, which manages the toggle bit itself.
+    pub fn with_auto_toggle(samplerate: u32) -> Self {
+        Self {
+            auto_toggle: true,
+            ..Self::new(samplerate)
+        }
+    }
+
+    /// Send the last loaded command again, as a repeat of the same press
+    pub fn repeat(&mut self) {
+        if let Some(cmd) = self.cmd {
+            self.frame.load(|buf| Self::encode(&cmd, buf), 0);
+        }
+    }
+}
+
+impl Sender<Rc6Cmd> for Rc6Sender {
+    fn load(&mut self, mut cmd: Rc6Cmd) {
+        if self.auto_toggle {
+            self.toggle = !self.toggle;
+            cmd.toggle = self.toggle;
+        }
+
+        self.cmd = Some(cmd);
+        self.repeat();
+    }
+
+    fn step(&mut self, ts: u32) -> State {
+        self.frame.step(ts)
+    }
+
+    fn reset(&mut self) {
+        self.frame.reset();
+        self.cmd = None;
+    }
+
+    fn carrier(&self) -> Carrier {
+        Self::CARRIER
+    }
+}
+
+impl PulseSender<Rc6Cmd> for Rc6Sender {
+    fn encode<T: TryFrom<u32>>(cmd: &Rc6Cmd, buf: &mut PulseBuffer<T>) {
+        // Leader
+        buf.mark(6 * UNIT);
+        buf.space(2 * UNIT);
+
+        // Start bit and mode 0
+        bit(buf, true, UNIT);
+        for _ in 0..3 {
+            bit(buf, false, UNIT);
+        }
+
+        // Trailer bit, double length
+        bit(buf, cmd.toggle, 2 * UNIT);
+
+        let bits = u16::from(cmd.addr) << 8 | u16::from(cmd.cmd);
+        for i in (0..16).rev() {
+            bit(buf, bits & (1 << i) != 0, UNIT);
+        }
+    }
+
+    fn carrier(_cmd: &Rc6Cmd) -> Carrier {
+        Self::CARRIER
+    }
+}
+
+/// Manchester coded, a one is sent as mark then space
+fn bit<T: TryFrom<u32>>(buf: &mut PulseBuffer<T>, one: bool, len: u32) {
+    if one {
+        buf.mark(len);
+        buf.space(len);
+    } else {
+        buf.space(len);
+        buf.mark(len);
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl crate::sender::PwmPinSender<Rc6Cmd> for Rc6Sender {}
@@ -16,6 +16,9 @@ use crate::{
     Command,
 };
 
+mod send;
+pub use send::SbpSender;
+
 #[derive(Debug)]
 pub struct Sbp {
     state: SbpState,
@@ -23,9 +26,12 @@ pub struct Sbp {
     command: u32,
     since_rising: u32,
     ranges: PulseWidthRange<SbpPulse>,
+
+    #[cfg(feature = "diagnostics")]
+    diag: crate::Diagnostics,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct SbpCommand {
     pub address: u16,
     pub command: u8,
@@ -38,7 +44,7 @@ impl SbpCommand {
         command >>= 4;
 
         // Check the checksum
-        let valid = (((command >> 0) ^ (command >> 8)) & 0xFF) == 0xFF;
+        let valid = ((command ^ (command >> 8)) & 0xFF) == 0xFF;
 
         Self {
             address,
@@ -70,10 +76,11 @@ impl Command for SbpCommand {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 // Internal receiver state
 pub enum SbpState {
     // Waiting for first pulse
+    #[default]
     Init,
     // Receiving address
     Address(u16),
@@ -87,12 +94,6 @@ pub enum SbpState {
     Err(Error),
 }
 
-impl Default for SbpState {
-    fn default() -> Self {
-        Self::Init
-    }
-}
-
 impl From<SbpState> for State {
     fn from(state: SbpState) -> State {
         use SbpState::*;
@@ -115,11 +116,40 @@ impl Default for Sbp {
             address: 0,
             command: 0,
             since_rising: 0,
+            #[cfg(feature = "diagnostics")]
+            diag: ranges.diagnostics(TIMING.data),
             ranges,
         }
     }
 }
 
+#[cfg(feature = "diagnostics")]
+impl Sbp {
+    /// Add the symbol of `pw` micro seconds to the diagnostics
+    fn diagnose(&mut self, pw: u32) {
+        let class = self.ranges.class(pw);
+
+        match self.state {
+            SbpState::Init if class == Some(0) => self.diag.start(),
+            SbpState::Address(_) | SbpState::Divider | SbpState::Command(_) => {}
+            _ => return,
+        }
+
+        self.diag.symbol(class, pw);
+    }
+}
+
+impl Sbp {
+    /// Done if the command matches its inverted copy
+    fn check(&self) -> SbpState {
+        if SbpCommand::from_receiver(self.address, self.command).valid {
+            SbpState::Done
+        } else {
+            SbpState::Err(Error::Checksum)
+        }
+    }
+}
+
 impl ReceiverSM for Sbp {
     type Cmd = SbpCommand;
     type InternalState = SbpState;
@@ -137,6 +167,9 @@ impl ReceiverSM for Sbp {
             let dt = self.since_rising + dt;
             let pulsewidth = self.ranges.pulsewidth(dt);
 
+            #[cfg(feature = "diagnostics")]
+            self.diagnose(dt);
+
             self.state = match (self.state, pulsewidth) {
                 (Init, Sync) => Address(0),
                 (Init, _) => Init,
@@ -151,22 +184,22 @@ impl ReceiverSM for Sbp {
                     Address(bit + 1)
                 }
                 (Address(bit), Zero) => Address(bit + 1),
-                (Address(_), _) => Err(Error::Address),
+                (Address(bit), _) => Err(Error::pulse_width(bit as u8, dt, Self::MAX_GAP)),
 
                 (Divider, Paus) => Command(0),
-                (Divider, _) => Err(Error::Data),
+                (Divider, _) => Err(Error::pulse_width(16, dt, Self::MAX_GAP)),
 
                 (Command(19), One) => {
                     self.command |= 1 << 19;
-                    Done
+                    self.check()
                 }
-                (Command(19), Zero) => Done,
+                (Command(19), Zero) => self.check(),
                 (Command(bit), One) => {
                     self.command |= 1 << bit;
                     Command(bit + 1)
                 }
                 (Command(bit), Zero) => Command(bit + 1),
-                (Command(_), _) => Err(Error::Data),
+                (Command(bit), _) => Err(Error::pulse_width(17 + bit as u8, dt, Self::MAX_GAP)),
 
                 (Done, _) => Done,
                 (Err(err), _) => Err(err),
@@ -182,6 +215,11 @@ impl ReceiverSM for Sbp {
         Some(SbpCommand::from_receiver(self.address, self.command))
     }
 
+    #[cfg(feature = "diagnostics")]
+    fn diagnostics(&self) -> Option<&crate::Diagnostics> {
+        Some(&self.diag)
+    }
+
     fn reset(&mut self) {
         self.state = SbpState::Init;
         self.address = 0;
@@ -222,21 +260,16 @@ const TIMING: SbpTiming = SbpTiming {
     one: 1500,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum SbpPulse {
     Sync = 0,
     Paus = 1,
     Zero = 2,
     One = 3,
+    #[default]
     NotAPulseWidth = 4,
 }
 
-impl Default for SbpPulse {
-    fn default() -> Self {
-        SbpPulse::NotAPulseWidth
-    }
-}
-
 impl From<usize> for SbpPulse {
     fn from(v: usize) -> Self {
         match v {
@@ -248,3 +281,63 @@ impl From<usize> for SbpPulse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Sbp, SbpCommand, SbpSender};
+    use crate::recv::Error;
+    use crate::sender::PulseSender;
+    use crate::DurationReceiver;
+
+    /// Frame with address 0x1234 and command 0x56, and its number of pulses
+    fn frame() -> ([u32; 77], usize) {
+        let cmd = SbpCommand {
+            address: 0x1234,
+            command: 0x56,
+            valid: true,
+        };
+        let mut pulses = [0u32; 77];
+        let n = SbpSender::pulses(&cmd, &mut pulses).unwrap();
+        (pulses, n)
+    }
+
+    #[test]
+    fn checksum() {
+        let (mut pulses, n) = frame();
+
+        let mut recv: DurationReceiver<Sbp> = DurationReceiver::new();
+        let mut received = None;
+        for (i, us) in pulses[..n].iter().enumerate() {
+            received = received.or(recv.event_us(i & 0x1 == 1, *us).unwrap());
+        }
+        let received = received.unwrap();
+        assert_eq!((received.address, received.command), (0x1234, 0x56));
+
+        // Flip the last bit of the inverted command
+        pulses[n - 2] = if pulses[n - 2] == 500 { 1500 } else { 500 };
+
+        let err = pulses[..n]
+            .iter()
+            .enumerate()
+            .find_map(|(i, us)| recv.event_us(i & 0x1 == 1, *us).err());
+        assert_eq!(err, Some(Error::Checksum));
+    }
+
+    #[test]
+    fn error_bits() {
+        let (pulses, n) = frame();
+
+        // Spaces of the divider and of the first command bit, after the sync and the address
+        let mut recv: DurationReceiver<Sbp> = DurationReceiver::new();
+        for (space, bit) in [(35, 16), (37, 17)] {
+            let mut bad = pulses;
+            bad[space] = 3000;
+
+            let err = bad[..n]
+                .iter()
+                .enumerate()
+                .find_map(|(i, us)| recv.event_us(i & 0x1 == 1, *us).err());
+            assert!(matches!(err, Some(Error::PulseWidth { bit: b, .. }) if b == bit));
+        }
+    }
+}
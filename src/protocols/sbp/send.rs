@@ -0,0 +1,81 @@
+use crate::{
+    protocols::sbp::{SbpCommand, TIMING},
+    sender::{FrameStepper, PulseBuffer, PulseSender, Sender, State},
+    Carrier, Protocol,
+};
+use core::convert::TryFrom;
+
+/// Max number of mark and space durations in a frame
+const PULSES: usize = 77;
+
+pub struct SbpSender {
+    frame: FrameStepper<PULSES>,
+}
+
+impl SbpSender {
+    pub const CARRIER: Carrier = match Protocol::Sbp.carrier() {
+        Some(carrier) => carrier,
+        None => panic!("Sbp has a known carrier"),
+    };
+
+    pub fn new(samplerate: u32) -> Self {
+        Self {
+            frame: FrameStepper::new(samplerate),
+        }
+    }
+}
+
+impl Sender<SbpCommand> for SbpSender {
+    fn load(&mut self, cmd: SbpCommand) {
+        self.frame.load(|buf| Self::encode(&cmd, buf), 0);
+    }
+
+    fn step(&mut self, ts: u32) -> State {
+        self.frame.step(ts)
+    }
+
+    fn reset(&mut self) {
+        self.frame.reset();
+    }
+
+    fn carrier(&self) -> Carrier {
+        Self::CARRIER
+    }
+}
+
+impl PulseSender<SbpCommand> for SbpSender {
+    fn encode<T: TryFrom<u32>>(cmd: &SbpCommand, buf: &mut PulseBuffer<T>) {
+        buf.mark(TIMING.hh);
+        buf.space(TIMING.hl);
+
+        bits(buf, cmd.address.into(), 16);
+
+        buf.mark(TIMING.data);
+        buf.space(TIMING.paus);
+
+        // Four unknown bits, then the command and the inverted command
+        let command = u32::from(cmd.command);
+        bits(buf, (command | (!command & 0xFF) << 8) << 4, 20);
+
+        buf.mark(TIMING.data);
+    }
+
+    fn carrier(_cmd: &SbpCommand) -> Carrier {
+        Self::CARRIER
+    }
+}
+
+/// Pulse distance coded, lsb first
+fn bits<T: TryFrom<u32>>(buf: &mut PulseBuffer<T>, value: u32, n: u32) {
+    for bit in 0..n {
+        buf.mark(TIMING.data);
+        buf.space(if value & (1 << bit) != 0 {
+            TIMING.one
+        } else {
+            TIMING.zero
+        });
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl crate::sender::PwmPinSender<SbpCommand> for SbpSender {}
@@ -24,11 +24,21 @@ where
     }
 
     pub fn pulsewidth(&self, pulsewidth: u32) -> T {
-        self.r
-            .iter()
-            .position(|r| r.contains(&pulsewidth))
-            .map(T::from)
-            .unwrap_or_default()
+        self.class(pulsewidth).map(T::from).unwrap_or_default()
+    }
+
+    /// Index of the range containing `pulsewidth`
+    pub fn class(&self, pulsewidth: u32) -> Option<usize> {
+        self.r.iter().position(|r| r.contains(&pulsewidth))
+    }
+
+    #[cfg(feature = "diagnostics")]
+    pub fn diagnostics(&self, unit: u32) -> crate::Diagnostics {
+        let classes = self
+            .r
+            .clone()
+            .map(|r| ((r.start + r.end) / 2, r.start, r.end - 1));
+        crate::Diagnostics::new(unit, &classes)
     }
 }
 
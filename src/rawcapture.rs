@@ -0,0 +1,208 @@
+//! Raw capture of unknown remotes
+
+use crate::recv::TickConverter;
+
+/// Default idle gap that ends a frame, in micro seconds
+const IDLE_US: u32 = 20_000;
+
+/// Receiver that records the mark and space durations of a frame, for learning unknown remotes
+///
+/// The frame is recorded in micro seconds, starting and ending with a mark, until the line has
+/// been idle for the idle gap. The frame can be replayed with a `RawSender`.
+///
+/// Edges are added with `edge_event`, like with an `EventReceiver`, or with `poll`, like with
+/// a `PeriodicReceiver`.
+pub struct RawCapture<const N: usize> {
+    buf: [u32; N],
+    len: usize,
+    ticks: TickConverter,
+    /// Idle gap that ends a frame, in micro seconds
+    idle: u32,
+    /// A frame is being recorded
+    capturing: bool,
+    /// The recorded frame is complete
+    done: bool,
+    /// The edge that completed the frame started the next one
+    restart: bool,
+    /// The frame didn't fit in the buffer
+    truncated: bool,
+    /// Last level and timestamp, for `poll`
+    level: bool,
+    last: u32,
+}
+
+impl<const N: usize> RawCapture<N> {
+    /// Create a new capture for edges at `samplerate`, with the default idle gap of 20 ms
+    pub fn new(samplerate: u32) -> Self {
+        Self::with_idle_gap(samplerate, IDLE_US)
+    }
+
+    /// Create a new capture for edges at `samplerate`, ending frames after `idle_us` micro
+    /// seconds without edges
+    pub fn with_idle_gap(samplerate: u32, idle_us: u32) -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            ticks: TickConverter::new(samplerate),
+            idle: idle_us,
+            capturing: false,
+            done: false,
+            restart: false,
+            truncated: false,
+            level: false,
+            last: 0,
+        }
+    }
+
+    /// Add an edge, `dt` samples after the previous. `edge` is true at the start of a mark.
+    ///
+    /// Returns the frame when it's complete. It's kept until the next edge.
+    pub fn edge_event(&mut self, edge: bool, dt: u32) -> Option<&[u32]> {
+        let dt_us = self.ticks.to_us(dt);
+
+        if self.done {
+            self.done = false;
+            self.capturing = self.restart;
+            self.clear();
+        }
+
+        if edge {
+            if !self.capturing {
+                // First mark of a frame
+                self.capturing = true;
+                self.clear();
+                return None;
+            }
+
+            if dt_us > self.idle {
+                // The gap ended the frame, and this edge starts the next one
+                self.done = true;
+                self.restart = true;
+                return self.frame();
+            }
+        } else if !self.capturing {
+            return None;
+        }
+
+        self.push(dt_us);
+        None
+    }
+
+    /// Check if the frame has ended, `idle` samples after the last edge
+    pub fn timeout_check(&mut self, idle: u32) -> Option<&[u32]> {
+        if !self.capturing || self.done || self.len == 0 || self.ticks.to_us(idle) <= self.idle {
+            return None;
+        }
+
+        self.done = true;
+        self.restart = false;
+        self.frame()
+    }
+
+    /// Sample the line, `edge` is true during marks. Should be called at the samplerate.
+    pub fn poll(&mut self, edge: bool, ts: u32) -> Option<&[u32]> {
+        let dt = ts.wrapping_sub(self.last);
+
+        if edge == self.level {
+            return self.timeout_check(dt);
+        }
+
+        self.level = edge;
+        self.last = ts;
+        self.edge_event(edge, dt)
+    }
+
+    /// The recorded frame, if complete
+    pub fn frame(&self) -> Option<&[u32]> {
+        if self.done {
+            Some(&self.buf[..self.len])
+        } else {
+            None
+        }
+    }
+
+    /// The frame was longer than the buffer, and was cut short
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Drop the recorded frame
+    pub fn reset(&mut self) {
+        self.capturing = false;
+        self.done = false;
+        self.clear();
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+
+    fn push(&mut self, us: u32) {
+        match self.buf.get_mut(self.len) {
+            Some(slot) => {
+                *slot = us;
+                self.len += 1;
+            }
+            None => self.truncated = true,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "nec")]
+mod tests {
+    use super::RawCapture;
+    use crate::protocols::nec;
+
+    #[test]
+    fn capture() {
+        let pulses = nec::tests::frame();
+        let pulses = &pulses[..];
+
+        let mut capture: RawCapture<128> = RawCapture::new(1_000_000);
+        let mut ts = 5000;
+
+        for _ in 0..2 {
+            for (i, dt) in pulses.iter().enumerate() {
+                assert!(capture.poll(i & 0x1 == 0, ts).is_none());
+                ts += dt;
+            }
+            assert!(capture.poll(false, ts).is_none());
+
+            // Not idle for long enough
+            assert!(capture.poll(false, ts + 20_000).is_none());
+            assert_eq!(capture.poll(false, ts + 20_001), Some(pulses));
+            assert!(!capture.truncated());
+
+            ts += 40_000;
+        }
+
+        // Back to back frames, ended by the next one
+        let mut capture: RawCapture<128> = RawCapture::new(1_000_000);
+        let mut frames = 0;
+        for _ in 0..3 {
+            let mut dt = 40_000;
+            for (i, pulse) in pulses.iter().enumerate() {
+                if let Some(frame) = capture.edge_event(i & 0x1 == 0, dt) {
+                    assert_eq!(frame, pulses);
+                    frames += 1;
+                }
+                dt = *pulse;
+            }
+            // End of the last mark
+            assert!(capture.edge_event(false, dt).is_none());
+        }
+        assert_eq!(frames, 2);
+
+        let mut small: RawCapture<16> = RawCapture::new(1_000_000);
+        let mut ts = 0;
+        for (i, dt) in pulses.iter().enumerate() {
+            small.poll(i & 0x1 == 0, ts);
+            ts += dt;
+        }
+        small.poll(false, ts);
+        assert_eq!(small.timeout_check(30_000).map(|f| f.len()), Some(16));
+        assert!(small.truncated());
+    }
+}
@@ -1,12 +1,18 @@
 //! Receiver
 
+use core::convert::TryFrom;
+
 use crate::Command;
 
 /// Event driven receiver
 pub struct EventReceiver<SM> {
     pub sm: SM,
     /// Receiver running at samplerate
-    precalc_multiplier: u32,
+    ticks: TickConverter,
+    /// A frame is being received
+    receiving: bool,
+    #[cfg(feature = "stats")]
+    stats: crate::Statistics,
 }
 
 /// Receiver - event based
@@ -15,35 +21,196 @@ impl<SM: ReceiverSM> EventReceiver<SM> {
     pub fn new(samplerate: u32) -> Self {
         Self {
             sm: SM::create(),
-            precalc_multiplier: 1_000_000 / samplerate,
+            ticks: TickConverter::new(samplerate),
+            receiving: false,
+            #[cfg(feature = "stats")]
+            stats: crate::Statistics::default(),
         }
     }
 
     /// Event happened
     pub fn edge_event(&mut self, edge: bool, delta_samples: u32) -> Result<Option<SM::Cmd>, Error> {
-        // Convert to micro seconds
-        let dt_us = delta_samples * self.precalc_multiplier;
+        let dt_us = self.ticks.to_us(delta_samples);
+        let res = sm_event(&mut self.sm, &mut self.receiving, edge, dt_us);
 
-        // Update state machine
-        let state: State = self.sm.event(edge, dt_us).into();
+        #[cfg(feature = "stats")]
+        {
+            self.stats.result(&res);
+            self.stats.glitches(self.sm.take_glitches());
+        }
 
-        match state {
-            State::Done => {
-                let cmd = self.sm.command();
-                self.sm.reset();
-                Ok(cmd)
-            }
-            State::Error(err) => {
-                self.sm.reset();
-                Err(err)
-            }
-            State::Idle | State::Receiving => Ok(None),
+        res
+    }
+
+    /// Check if the frame being received has timed out, `idle_samples` after the last edge
+    ///
+    /// A frame that has been idle for longer than the maximum gap of the protocol is dropped, or
+    /// returned if the protocol ends its frames with the gap.
+    pub fn timeout_check(&mut self, idle_samples: u32) -> Option<SM::Cmd> {
+        let idle_us = self.ticks.to_us(idle_samples);
+
+        #[cfg(feature = "stats")]
+        let receiving = self.receiving;
+
+        let cmd = sm_timeout(&mut self.sm, &mut self.receiving, idle_us);
+
+        #[cfg(feature = "stats")]
+        if receiving && !self.receiving {
+            self.stats.timeout(cmd.as_ref());
         }
+
+        cmd
     }
 
     /// Reset receiver
     pub fn reset(&mut self) {
         self.sm.reset();
+        self.receiving = false;
+    }
+
+    /// Snapshot of the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::Statistics {
+        self.stats
+    }
+
+    /// Reset the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.stats = crate::Statistics::default();
+    }
+}
+
+/// Receiver for durations in microseconds or nanoseconds, for timestamps from a clock that
+/// already counts in time units
+pub struct DurationReceiver<SM> {
+    pub sm: SM,
+    /// A frame is being received
+    receiving: bool,
+}
+
+impl<SM: ReceiverSM> DurationReceiver<SM> {
+    pub fn new() -> Self {
+        Self {
+            sm: SM::create(),
+            receiving: false,
+        }
+    }
+
+    /// Event happened `dt_us` microseconds after the previous
+    pub fn event_us(&mut self, edge: bool, dt_us: u32) -> Result<Option<SM::Cmd>, Error> {
+        sm_event(&mut self.sm, &mut self.receiving, edge, dt_us)
+    }
+
+    /// Event happened `dt_ns` nanoseconds after the previous
+    pub fn event_ns(&mut self, edge: bool, dt_ns: u64) -> Result<Option<SM::Cmd>, Error> {
+        self.event_us(edge, ns_to_us(dt_ns))
+    }
+
+    /// Check if the frame being received has timed out, `idle_us` microseconds after the last
+    /// edge
+    pub fn timeout_check(&mut self, idle_us: u32) -> Option<SM::Cmd> {
+        sm_timeout(&mut self.sm, &mut self.receiving, idle_us)
+    }
+
+    /// Reset receiver
+    pub fn reset(&mut self) {
+        self.sm.reset();
+        self.receiving = false;
+    }
+}
+
+fn ns_to_us(ns: u64) -> u32 {
+    u32::try_from(ns / 1000).unwrap_or(u32::MAX)
+}
+
+impl<SM: ReceiverSM> Default for DurationReceiver<SM> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Update the state machine with an event `dt_us` after the previous
+fn sm_event<SM: ReceiverSM>(
+    sm: &mut SM,
+    receiving: &mut bool,
+    edge: bool,
+    dt_us: u32,
+) -> Result<Option<SM::Cmd>, Error> {
+    let state: State = sm.event(edge, dt_us).into();
+    *receiving = state == State::Receiving;
+
+    match state {
+        State::Done => {
+            let cmd = sm.command();
+            sm.reset();
+            Ok(cmd)
+        }
+        State::Error(err) => {
+            sm.reset();
+            Err(err)
+        }
+        State::Idle | State::Receiving => Ok(None),
+    }
+}
+
+/// Time out the frame being received if the line has been idle for longer than the max gap
+fn sm_timeout<SM: ReceiverSM>(sm: &mut SM, receiving: &mut bool, idle_us: u32) -> Option<SM::Cmd> {
+    if !*receiving || idle_us <= SM::MAX_GAP {
+        return None;
+    }
+
+    *receiving = false;
+    sm.timeout()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Converts between timer ticks and microseconds, using the exact ratio of the tickrate and
+/// 1 MHz
+///
+/// Works for any tickrate, also ones that don't divide 1 MHz evenly, such as 32768 Hz RTCs and
+/// high frequency cycle counters.
+pub struct TickConverter {
+    num: u32,
+    den: u32,
+}
+
+impl TickConverter {
+    /// Converter for a timer running at `tickrate` Hz
+    pub const fn new(tickrate: u32) -> Self {
+        let div = gcd(1_000_000, tickrate);
+
+        Self {
+            num: 1_000_000 / div,
+            den: tickrate / div,
+        }
+    }
+
+    /// Ticks to microseconds, rounded down. Saturates at `u32::MAX`.
+    pub const fn to_us(&self, ticks: u32) -> u32 {
+        saturate(ticks as u64 * self.num as u64 / self.den as u64)
+    }
+
+    /// Microseconds to ticks, rounded down. Saturates at `u32::MAX`.
+    pub const fn from_us(&self, us: u32) -> u32 {
+        saturate(us as u64 * self.den as u64 / self.num as u64)
+    }
+}
+
+const fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+const fn saturate(v: u64) -> u32 {
+    if v > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        v as u32
     }
 }
 
@@ -67,7 +234,7 @@ impl<SM: ReceiverSM> PeriodicReceiver<SM> {
 
     pub fn poll(&mut self, edge: bool, ts: u32) -> Result<Option<SM::Cmd>, Error> {
         if self.edge == edge {
-            return Ok(None);
+            return Ok(self.timeout_check(ts));
         }
 
         let dt = ts.wrapping_sub(self.last);
@@ -77,17 +244,38 @@ impl<SM: ReceiverSM> PeriodicReceiver<SM> {
         self.recv.edge_event(edge, dt)
     }
 
+    /// Check if the frame being received has timed out at timestamp `now`
+    ///
+    /// Called by `poll` when the pin hasn't changed
+    pub fn timeout_check(&mut self, now: u32) -> Option<SM::Cmd> {
+        self.recv.timeout_check(now.wrapping_sub(self.last))
+    }
+
     pub fn reset(&mut self) {
         self.recv.reset()
     }
+
+    /// Snapshot of the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> crate::Statistics {
+        self.recv.stats()
+    }
+
+    /// Reset the statistics counters
+    #[cfg(feature = "stats")]
+    pub fn reset_stats(&mut self) {
+        self.recv.reset_stats()
+    }
 }
 
 /// Receiver for decoding a captured pulse train
+///
+/// Frames with errors are skipped.
 pub struct BufferedReceiver<'a, SM> {
     sm: SM,
     buf: &'a [u32],
     i: usize,
-    precalc_mult: u32,
+    ticks: TickConverter,
 }
 
 impl<'a, SM: ReceiverSM> BufferedReceiver<'a, SM> {
@@ -96,7 +284,7 @@ impl<'a, SM: ReceiverSM> BufferedReceiver<'a, SM> {
             buf,
             i: 0,
             sm: SM::create(),
-            precalc_mult: 1_000_000 / samplerate,
+            ticks: TickConverter::new(samplerate),
         }
     }
 }
@@ -112,10 +300,16 @@ impl<'a, SM: ReceiverSM> Iterator for BufferedReceiver<'a, SM> {
             }
 
             let pos_edge = self.i & 0x1 == 0;
-            let dt_us = self.buf[self.i] * self.precalc_mult;
+            let dt_us = self.ticks.to_us(self.buf[self.i]);
             self.i += 1;
 
-            let state: State = self.sm.event(pos_edge, dt_us).into();
+            let mut state: State = self.sm.event(pos_edge, dt_us).into();
+
+            if let State::Error(_) = state {
+                // The edge that broke the frame could be the start of the next one
+                self.sm.reset();
+                state = self.sm.event(pos_edge, dt_us).into();
+            }
 
             match state {
                 State::Idle | State::Receiving => {
@@ -128,7 +322,7 @@ impl<'a, SM: ReceiverSM> Iterator for BufferedReceiver<'a, SM> {
                 }
                 State::Error(_) => {
                     self.sm.reset();
-                    break None;
+                    continue;
                 }
             }
         }
@@ -156,12 +350,37 @@ pub trait ReceiverSM {
 
     /// Reset the state machine
     fn reset(&mut self);
+
+    /// Longest time without edges within a frame, in micro seconds
+    const MAX_GAP: u32 = 10_000;
+
+    /// The line has been idle for longer than `MAX_GAP` while receiving
+    ///
+    /// Protocols that end their frames with a gap return the command here. By default the frame
+    /// is dropped.
+    fn timeout(&mut self) -> Option<Self::Cmd> {
+        self.reset();
+        None
+    }
+
+    /// Timing diagnostics of the last frame, kept until the next frame starts
+    #[cfg(feature = "diagnostics")]
+    fn diagnostics(&self) -> Option<&crate::Diagnostics> {
+        None
+    }
+
+    /// Number of glitches dropped since the last call
+    #[cfg(feature = "stats")]
+    fn take_glitches(&mut self) -> u32 {
+        0
+    }
 }
 
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, Default)]
 /// Protocol decoder state
 pub enum State {
     /// Idle
+    #[default]
     Idle,
     /// Receiving data
     Receiving,
@@ -171,19 +390,64 @@ pub enum State {
     Error(Error),
 }
 
-impl Default for State {
-    fn default() -> State {
-        State::Idle
-    }
-}
-
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 /// Receive error
+///
+/// Bits are numbered in the order they are sent, starting with the first bit after the header.
+#[non_exhaustive]
 pub enum Error {
     /// Error while decoding address
     Address,
     /// Error decoding data bits
     Data,
+    /// The header didn't match the protocol
+    Header,
+    /// Pulse width, in micro seconds, that isn't a symbol of the protocol
+    PulseWidth { bit: u8, width: u32 },
+    /// The checksum, or the inverted copy of the address or command, didn't match
+    Checksum,
+    /// Edge in the wrong direction
+    UnexpectedEdge { bit: u8 },
+    /// No edge for longer than the max gap of the protocol
+    Timeout { bit: u8 },
     /// Error receiver specific error
     Other,
 }
+
+impl Error {
+    /// Error for a pulse width of `width` micro seconds at `bit`. Widths longer than `max_gap`
+    /// are a timeout.
+    #[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+    pub(crate) fn pulse_width(bit: u8, width: u32, max_gap: u32) -> Self {
+        if width > max_gap {
+            Error::Timeout { bit }
+        } else {
+            Error::PulseWidth { bit, width }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TickConverter;
+
+    #[test]
+    fn tick_converter() {
+        // Cycle counter at 72 MHz, a 9 ms Nec header
+        let cyccnt = TickConverter::new(72_000_000);
+        assert_eq!(cyccnt.to_us(648_000), 9000);
+        assert_eq!(cyccnt.from_us(9000), 648_000);
+        assert_eq!(cyccnt.to_us(u32::MAX), 59_652_323);
+
+        // 32768 Hz rtc
+        let rtc = TickConverter::new(32768);
+        assert_eq!(rtc.to_us(295), 9002);
+        assert_eq!(rtc.to_us(u32::MAX), u32::MAX);
+
+        let mhz3 = TickConverter::new(3_000_000);
+        assert_eq!(mhz3.to_us(27_000), 9000);
+
+        let khz40 = TickConverter::new(40_000);
+        assert_eq!(khz40.to_us(363), 9075);
+    }
+}
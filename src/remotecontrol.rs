@@ -1,6 +1,6 @@
-use crate::Command;
+use crate::{Command, Protocol};
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// Device type that the remote control controls
 pub enum DeviceType {
     Generic,
@@ -16,29 +16,294 @@ pub trait RemoteControl {
     const MODEL: &'static str = "<NONAME>";
     /// Type of device that this remote controls
     const DEVTYPE: DeviceType = DeviceType::Generic;
-    /// Device address
+    /// Protocol used by the buttons that doesn't specify their own
+    const PROTOCOL: Protocol;
+    /// Address used by the buttons that doesn't specify their own
     const ADDRESS: u32;
     /// The type of command
     type Cmd: Command;
-    /// command byte to standardbutton mapping
-    const BUTTONS: &'static [(u8, Button)] = &[];
-    /// Try to map a command into an Button for this remote
-    fn decode(cmd: Self::Cmd) -> Option<Button> {
-        // Check address
-        if Self::ADDRESS != cmd.address().into() {
-            return None;
-        }
+    /// Command to standardbutton mapping
+    const BUTTONS: &'static [ButtonMapping] = &[];
+
+    /// Find the button sent as `cmd` to `address` using `protocol`
+    fn lookup(protocol: Protocol, address: u32, cmd: u32) -> Option<Button> {
         Self::BUTTONS
             .iter()
-            .find(|(c, _)| u32::from(*c) == cmd.data())
-            .map(|(_, b)| *b)
+            .find(|m| {
+                m.protocol.unwrap_or(Self::PROTOCOL) == protocol
+                    && m.address.unwrap_or(Self::ADDRESS) == address
+                    && m.cmd == cmd
+            })
+            .map(|m| m.button)
+    }
+    /// Try to map a command into an Button for this remote
+    fn decode(cmd: Self::Cmd) -> Option<Button> {
+        Self::decode_any(&cmd)
+    }
+    /// Try to map a command of any protocol into a Button for this remote
+    fn decode_any<C: Command + ?Sized>(cmd: &C) -> Option<Button> {
+        Self::lookup(cmd.protocol(), cmd.address(), cmd.data())
     }
     /// Encode a button into a command
+    ///
+    /// Only buttons sent with the protocol of `Cmd` can be encoded
     fn encode(button: Button) -> Option<Self::Cmd> {
         Self::BUTTONS
             .iter()
-            .find(|(_, b)| *b == button)
-            .and_then(|(c, _)| Self::Cmd::construct(Self::ADDRESS, *c as u32))
+            .filter(|m| {
+                m.button == button && m.protocol.unwrap_or(Self::PROTOCOL) == Self::PROTOCOL
+            })
+            .find_map(|m| Self::Cmd::construct(m.address.unwrap_or(Self::ADDRESS), m.cmd))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Mapping of a command to a button
+pub struct ButtonMapping {
+    /// Protocol, or `None` for the protocol of the remote
+    pub protocol: Option<Protocol>,
+    /// Address, or `None` for the address of the remote
+    pub address: Option<u32>,
+    /// Command
+    pub cmd: u32,
+    /// The button
+    pub button: Button,
+}
+
+impl ButtonMapping {
+    /// Button sent with the protocol and address of the remote
+    pub const fn new(cmd: u32, button: Button) -> Self {
+        Self {
+            protocol: None,
+            address: None,
+            cmd,
+            button,
+        }
+    }
+
+    /// Button sent to `address`
+    pub const fn with_address(address: u32, cmd: u32, button: Button) -> Self {
+        Self {
+            protocol: None,
+            address: Some(address),
+            cmd,
+            button,
+        }
+    }
+
+    /// Button sent to `address` using `protocol`
+    pub const fn with_protocol(protocol: Protocol, address: u32, cmd: u32, button: Button) -> Self {
+        Self {
+            protocol: Some(protocol),
+            address: Some(address),
+            cmd,
+            button,
+        }
+    }
+}
+
+/// Define a remote control from a table of commands and buttons
+///
+/// Expands to a unit struct implementing [`RemoteControl`], together with the const lookup
+/// functions `button(protocol, address, cmd)` and `mapping(button)`.
+///
+/// A button entry is one of
+/// * `(cmd, Button)`: sent with the protocol and address of the remote
+/// * `(address, cmd, Button)`: sent to another address
+/// * `(Protocol, address, cmd, Button)`: sent using another protocol
+///
+/// Two entries with the same command, or the same button sent twice to the same address,
+/// are rejected at compile time. With the `std` feature the remote is also added to
+/// `remotes::std::REMOTES`.
+///
+/// ```
+/// # #[cfg(feature = "nec")] {
+/// use infrared::{protocols::nec::NecCommand, remotecontrol, Button, Protocol, RemoteControl};
+///
+/// remotecontrol! {
+///     /// My remote
+///     pub MyRemote {
+///         model: "My remote",
+///         devtype: TV,
+///         protocol: Nec,
+///         address: 7,
+///         cmd: NecCommand,
+///         buttons: [
+///             (2, Power),
+///             (7, VolumeUp),
+///             (11, VolumeDown),
+///             // Buttons for the settop box
+///             (8, 2, Power),
+///             (Rc5, 20, 53, Play),
+///         ]
+///     }
+/// }
+///
+/// assert_eq!(MyRemote::button(Protocol::Nec, 7, 7), Some(Button::VolumeUp));
+/// assert_eq!(MyRemote::button(Protocol::Rc5, 20, 53), Some(Button::Play));
+/// assert_eq!(MyRemote::mapping(Button::VolumeDown).map(|m| m.cmd), Some(11));
+/// assert_eq!(MyRemote::decode(NecCommand::new(8, 2)), Some(Button::Power));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! remotecontrol {
+    (@entry $cmd:literal, $button:ident) => {
+        $crate::ButtonMapping::new($cmd, $crate::Button::$button)
+    };
+    (@entry $address:literal, $cmd:literal, $button:ident) => {
+        $crate::ButtonMapping::with_address($address, $cmd, $crate::Button::$button)
+    };
+    (@entry $protocol:ident, $address:literal, $cmd:literal, $button:ident) => {
+        $crate::ButtonMapping::with_protocol(
+            $crate::Protocol::$protocol, $address, $cmd, $crate::Button::$button
+        )
+    };
+
+    (@pattern $p:ident, $a:literal; $cmd:literal, $button:ident) => {
+        ($crate::Protocol::$p, $a, $cmd)
+    };
+    (@pattern $p:ident, $a:literal; $address:literal, $cmd:literal, $button:ident) => {
+        ($crate::Protocol::$p, $address, $cmd)
+    };
+    (@pattern $p:ident, $a:literal; $protocol:ident, $address:literal, $cmd:literal, $button:ident) => {
+        ($crate::Protocol::$protocol, $address, $cmd)
+    };
+
+    (@button $cmd:literal, $button:ident) => {
+        $crate::Button::$button
+    };
+    (@button $address:literal, $cmd:literal, $button:ident) => {
+        $crate::Button::$button
+    };
+    (@button $protocol:ident, $address:literal, $cmd:literal, $button:ident) => {
+        $crate::Button::$button
+    };
+
+    (
+        $(#[$outer:meta])*
+        $vis:vis $name:ident {
+            model: $model:expr,
+            devtype: $devtype:ident,
+            protocol: $protocol:ident,
+            address: $address:literal,
+            cmd: $cmd:ty,
+            buttons: [ $( ( $($entry:tt),+ ) ),* $(,)? ] $(,)?
+        }
+    ) => {
+        $(#[$outer])*
+        $vis struct $name;
+
+        impl $crate::RemoteControl for $name {
+            const MODEL: &'static str = $model;
+            const DEVTYPE: $crate::DeviceType = $crate::DeviceType::$devtype;
+            const PROTOCOL: $crate::Protocol = $crate::Protocol::$protocol;
+            const ADDRESS: u32 = $address;
+            type Cmd = $cmd;
+            const BUTTONS: &'static [$crate::ButtonMapping] = &[
+                $( $crate::remotecontrol!(@entry $($entry),+) ),*
+            ];
+
+            fn lookup(protocol: $crate::Protocol, address: u32, cmd: u32) -> Option<$crate::Button> {
+                Self::button(protocol, address, cmd)
+            }
+        }
+
+        const _: () = $crate::check_buttons(
+            $crate::Protocol::$protocol,
+            $address,
+            <$name as $crate::RemoteControl>::BUTTONS,
+        );
+
+        impl $name {
+            /// Get the button sent as `cmd` to `address` using `protocol`
+            #[allow(unreachable_patterns)]
+            pub const fn button(
+                protocol: $crate::Protocol,
+                address: u32,
+                cmd: u32,
+            ) -> Option<$crate::Button> {
+                match (protocol, address, cmd) {
+                    $(
+                        $crate::remotecontrol!(@pattern $protocol, $address; $($entry),+)
+                            => Some($crate::remotecontrol!(@button $($entry),+)),
+                    )*
+                    _ => None,
+                }
+            }
+
+            /// Get the first mapping for `button`
+            #[allow(unreachable_patterns)]
+            pub const fn mapping(button: $crate::Button) -> Option<$crate::ButtonMapping> {
+                match button {
+                    $(
+                        $crate::remotecontrol!(@button $($entry),+)
+                            => Some($crate::remotecontrol!(@entry $($entry),+)),
+                    )*
+                    _ => None,
+                }
+            }
+        }
+
+        $crate::__register_remote!($name);
+    };
+}
+
+/// Adds a remote declared with `remotecontrol!` to `remotes::std::REMOTES`
+#[cfg(all(feature = "std", feature = "remotes"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_remote {
+    ($name:ident) => {
+        const _: () = {
+            #[$crate::__linkme::distributed_slice($crate::remotes::std::REMOTES)]
+            #[linkme(crate = $crate::__linkme)]
+            static REMOTE: $crate::remotes::std::RemoteControlData =
+                $crate::remotes::std::RemoteControlData::new::<$name>();
+        };
+    };
+}
+
+#[cfg(not(all(feature = "std", feature = "remotes")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register_remote {
+    ($name:ident) => {};
+}
+
+/// Panics, and thereby fails the build when used in a const context, if two mappings in
+/// `buttons` share a command, or if a button is sent twice to the same address
+#[doc(hidden)]
+pub const fn check_buttons(protocol: Protocol, address: u32, buttons: &[ButtonMapping]) {
+    const fn target(protocol: Protocol, address: u32, m: &ButtonMapping) -> (u32, u32) {
+        let p = match m.protocol {
+            Some(p) => p,
+            None => protocol,
+        };
+        let a = match m.address {
+            Some(a) => a,
+            None => address,
+        };
+        (p as u32, a)
+    }
+
+    let mut i = 0;
+    while i < buttons.len() {
+        let mut j = i + 1;
+        while j < buttons.len() {
+            let (pi, ai) = target(protocol, address, &buttons[i]);
+            let (pj, aj) = target(protocol, address, &buttons[j]);
+
+            if pi == pj && ai == aj {
+                if buttons[i].cmd == buttons[j].cmd {
+                    panic!("remote control has duplicate command codes");
+                }
+                if buttons[i].button as u32 == buttons[j].button as u32 {
+                    panic!("remote control has duplicate buttons");
+                }
+            }
+            j += 1;
+        }
+        i += 1;
     }
 }
 
@@ -111,3 +376,70 @@ pub enum Button {
     PitchMinus,
     Prog,
 }
+
+#[cfg(test)]
+#[cfg(all(feature = "nec", feature = "rc6"))]
+mod tests {
+    use crate::protocols::nec::{Nec16, NecCommand};
+    use crate::protocols::rc6::Rc6Cmd;
+    use crate::{Button, ButtonMapping, Protocol, RemoteControl};
+
+    remotecontrol! {
+        /// Tv with 16 bit Nec address and a Rc6 settop box
+        Combo {
+            model: "Combo",
+            devtype: TV,
+            protocol: Nec16,
+            address: 0x7F80,
+            cmd: NecCommand<Nec16>,
+            buttons: [
+                (2, Power),
+                (7, VolumeUp),
+                (0x7F81, 2, Source),
+                (Rc6, 70, 2, Play),
+                (Rc6, 70, 12, Power),
+            ]
+        }
+    }
+
+    /// Same remote without the generated lookups
+    struct ComboTable;
+
+    impl RemoteControl for ComboTable {
+        const PROTOCOL: Protocol = Protocol::Nec16;
+        const ADDRESS: u32 = 0x7F80;
+        type Cmd = NecCommand<Nec16>;
+        const BUTTONS: &'static [ButtonMapping] = <Combo as RemoteControl>::BUTTONS;
+    }
+
+    fn check<RC: RemoteControl<Cmd = NecCommand<Nec16>>>() {
+        assert_eq!(RC::decode(NecCommand::new(0x7F80, 2)), Some(Button::Power));
+        assert_eq!(RC::decode(NecCommand::new(0x7F81, 2)), Some(Button::Source));
+        assert_eq!(RC::decode(NecCommand::new(0x7F81, 7)), None);
+        assert_eq!(RC::decode_any(&Rc6Cmd::new(70, 12)), Some(Button::Power));
+        assert_eq!(RC::decode_any(&Rc6Cmd::new(70, 7)), None);
+
+        let source = RC::encode(Button::Source).unwrap();
+        assert_eq!((source.addr, source.cmd), (0x7F81, 2));
+        // Play is only sent using Rc6
+        assert!(RC::encode(Button::Play).is_none());
+    }
+
+    #[test]
+    fn multi_address() {
+        check::<Combo>();
+        check::<ComboTable>();
+
+        assert_eq!(Combo::button(Protocol::Rc6, 70, 2), Some(Button::Play));
+        assert_eq!(Combo::button(Protocol::Nec, 0x7F80, 2), None);
+        assert_eq!(
+            Combo::mapping(Button::Play),
+            Some(ButtonMapping::with_protocol(
+                Protocol::Rc6,
+                70,
+                2,
+                Button::Play
+            ))
+        );
+    }
+}
@@ -1,25 +1,22 @@
-use crate::protocols::sbp::SbpCommand;
+use crate::{protocols::sbp::SbpCommand, remotecontrol};
 
-use crate::remotecontrol::{Button, DeviceType, RemoteControl};
-
-use Button::*;
-
-pub struct SamsungBluRayPlayer;
-
-impl RemoteControl for SamsungBluRayPlayer {
-    const MODEL: &'static str = "Samsung BluRay Player";
-    const DEVTYPE: DeviceType = DeviceType::BluRayPlayer;
-    const ADDRESS: u32 = 32;
-    type Cmd = SbpCommand;
-    const BUTTONS: &'static [(u8, Button)] = &[
-        (2, One),
-        (3, Two),
-        (4, Three),
-        (5, Four),
-        (6, Five),
-        (7, Six),
-        (8, Seven),
-        (9, Eight),
-        (10, Nine),
-    ];
+remotecontrol! {
+    pub SamsungBluRayPlayer {
+        model: "Samsung BluRay Player",
+        devtype: BluRayPlayer,
+        protocol: Sbp,
+        address: 32,
+        cmd: SbpCommand,
+        buttons: [
+            (2, One),
+            (3, Two),
+            (4, Three),
+            (5, Four),
+            (6, Five),
+            (7, Six),
+            (8, Seven),
+            (9, Eight),
+            (10, Nine),
+        ]
+    }
 }
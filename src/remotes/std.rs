@@ -1,41 +1,192 @@
 use crate::{
-    remotecontrol::{Button, DeviceType, RemoteControl},
-    remotes::{
-        nec::{SamsungTv, SpecialForMp3},
-        rc5::Rc5CdPlayer,
-        sbp::SamsungBluRayPlayer,
-    },
+    remotecontrol::{Button, ButtonMapping, DeviceType, RemoteControl},
+    Command, Protocol,
 };
 
+/// All remote controls known by the library
+///
+/// Every remote declared with [`remotecontrol!`](crate::remotecontrol), in this crate or in the
+/// application using it, is added to the list at link time. The order of the list is unspecified.
+#[linkme::distributed_slice]
+pub static REMOTES: [RemoteControlData];
+
 pub fn remotes() -> Vec<RemoteControlData> {
-    // Pretty much every remote ever manufactured :-)
-    vec![
-        RemoteControlData::new::<Rc5CdPlayer>(),
-        RemoteControlData::new::<SamsungTv>(),
-        RemoteControlData::new::<SpecialForMp3>(),
-        RemoteControlData::new::<SamsungBluRayPlayer>(),
-    ]
+    REMOTES.to_vec()
 }
 
+/// Remote control that could have sent a set of commands
 #[derive(Debug)]
+pub struct RemoteMatch<'a> {
+    pub remote: &'a RemoteControlData,
+    /// The decoded button for each of the commands
+    pub buttons: Vec<Option<Button>>,
+    /// Number of commands decoded to a button
+    pub decoded: usize,
+    /// Number of commands sent with a protocol and address used by the remote
+    pub addressed: usize,
+}
+
+/// Identify the remote controls that could have sent `cmds`
+///
+/// The remotes are ranked by the number of commands decoded to a button, then by the number of
+/// commands sent to one of their addresses. Remotes that none of the commands are addressed to
+/// are left out.
+pub fn identify(cmds: &[&dyn Command]) -> Vec<RemoteMatch<'static>> {
+    identify_in(&REMOTES, cmds)
+}
+
+/// Identify the remote controls in `remotes` that could have sent `cmds`
+///
+/// ```
+/// # #[cfg(feature = "rc5")] {
+/// use infrared::{
+///     protocols::rc5::Rc5Command,
+///     remotes::{
+///         rc5::Rc5CdPlayer,
+///         std::{identify_in, RemoteControlData},
+///     },
+///     Button,
+/// };
+///
+/// static MY_REMOTES: &[RemoteControlData] = &[RemoteControlData::new::<Rc5CdPlayer>()];
+///
+/// let play = Rc5Command::new(20, 53, false);
+/// let matches = identify_in(MY_REMOTES, &[&play]);
+///
+/// assert_eq!(matches[0].remote.model, "CD Player (Rc5)");
+/// assert_eq!(matches[0].buttons, [Some(Button::Play)]);
+/// # }
+/// ```
+pub fn identify_in<'a>(
+    remotes: &'a [RemoteControlData],
+    cmds: &[&dyn Command],
+) -> Vec<RemoteMatch<'a>> {
+    let mut matches: Vec<RemoteMatch> = remotes
+        .iter()
+        .map(|remote| {
+            let buttons: Vec<_> = cmds.iter().map(|cmd| remote.decode(*cmd)).collect();
+
+            RemoteMatch {
+                remote,
+                decoded: buttons.iter().filter(|b| b.is_some()).count(),
+                addressed: cmds.iter().filter(|cmd| remote.addresses(**cmd)).count(),
+                buttons,
+            }
+        })
+        .filter(|m| m.addressed > 0)
+        .collect();
+
+    matches.sort_by_key(|m| core::cmp::Reverse((m.decoded, m.addressed)));
+    matches
+}
+
+#[derive(Debug, Clone)]
 pub struct RemoteControlData {
     pub model: &'static str,
     pub addr: u32,
-    //pub protocol: ProtocolId,
+    pub protocol: Protocol,
     pub dtype: DeviceType,
-    pub mapping: &'static [(u8, Button)],
+    pub mapping: &'static [ButtonMapping],
 }
 
 impl RemoteControlData {
-    pub fn new<R>() -> RemoteControlData
+    pub const fn new<R>() -> RemoteControlData
     where
         R: RemoteControl,
     {
         RemoteControlData {
             addr: R::ADDRESS,
+            protocol: R::PROTOCOL,
             model: R::MODEL,
             dtype: R::DEVTYPE,
             mapping: R::BUTTONS,
         }
     }
+
+    /// Try to map a command into a Button for this remote
+    pub fn decode<C: Command + ?Sized>(&self, cmd: &C) -> Option<Button> {
+        self.mapping
+            .iter()
+            .find(|m| self.target(m) == (cmd.protocol(), cmd.address()) && m.cmd == cmd.data())
+            .map(|m| m.button)
+    }
+
+    /// Is `cmd` sent with a protocol and address used by this remote
+    pub fn addresses<C: Command + ?Sized>(&self, cmd: &C) -> bool {
+        let target = (cmd.protocol(), cmd.address());
+        target == (self.protocol, self.addr)
+            || self.mapping.iter().any(|m| self.target(m) == target)
+    }
+
+    fn target(&self, m: &ButtonMapping) -> (Protocol, u32) {
+        (
+            m.protocol.unwrap_or(self.protocol),
+            m.address.unwrap_or(self.addr),
+        )
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "nec", feature = "rc5"))]
+mod tests {
+    use super::{identify, identify_in, RemoteControlData};
+    use crate::protocols::{
+        nec::{NecCommand, NecStandard},
+        rc5::Rc5Command,
+    };
+    use crate::{Button, Command};
+
+    #[test]
+    fn identify_remote() {
+        let play = Rc5Command::new(20, 53, false);
+        let stop = Rc5Command::new(20, 54, true);
+        let unknown = Rc5Command::new(20, 60, false);
+        let power: NecCommand = NecCommand::new(7, 2);
+
+        let cmds: [&dyn Command; 4] = [&play, &stop, &unknown, &power];
+        let matches = identify(&cmds);
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].remote.model, "CD Player (Rc5)");
+        assert_eq!(matches[0].decoded, 2);
+        assert_eq!(matches[0].addressed, 3);
+        assert_eq!(
+            matches[0].buttons,
+            [Some(Button::Play), Some(Button::Stop), None, None]
+        );
+        assert_eq!(matches[1].remote.model, "Samsung TV");
+        assert_eq!(matches[1].buttons, [None, None, None, Some(Button::Power)]);
+
+        let other: NecCommand<NecStandard> = NecCommand::new(99, 1);
+        assert!(identify(&[&other]).is_empty());
+    }
+
+    #[test]
+    fn register_own_remotes() {
+        crate::remotecontrol! {
+            Custom {
+                model: "Custom",
+                devtype: Generic,
+                protocol: Rc5,
+                address: 3,
+                cmd: Rc5Command,
+                buttons: [
+                    (1, One),
+                    (2, Two),
+                ],
+            }
+        }
+
+        let two = Rc5Command::new(3, 2, false);
+
+        let matches = identify(&[&two]);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].remote.model, "Custom");
+        assert_eq!(matches[0].buttons, [Some(Button::Two)]);
+        assert!(Custom::mapping(Button::Three).is_none());
+
+        static CUSTOM: &[RemoteControlData] = &[RemoteControlData::new::<Custom>()];
+        assert_eq!(identify_in(CUSTOM, &[&two]).len(), 1);
+        assert!(identify_in(&[], &[&two]).is_empty());
+    }
 }
@@ -1,6 +1,21 @@
 //! Transmitter state machine
 //!
 
+use core::convert::TryFrom;
+
+use crate::Carrier;
+
+#[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+mod any;
+#[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+pub use any::{AnyCommand, AnySender};
+
+mod queue;
+pub use queue::{Entry, TransmitQueue};
+
+mod raw;
+pub use raw::RawSender;
+
 #[derive(Debug)]
 /// Sender state
 pub enum State {
@@ -20,11 +35,247 @@ pub trait Sender<CMD> {
     fn step(&mut self, ts: u32) -> State;
     /// Reset the transmitter
     fn reset(&mut self);
+    /// The carrier the output should be modulated with
+    ///
+    /// Defaults to the common 38 kHz carrier with a 33% duty cycle, so that senders written
+    /// before the carrier was part of the trait keep working
+    fn carrier(&self) -> Carrier {
+        Carrier::new(38_000, 33)
+    }
+}
+
+/// Sender that encodes a command directly into mark and space durations, for hardware that
+/// takes a list of durations (timer DMA, RMT peripherals, LIRC) instead of being clocked
+pub trait PulseSender<CMD> {
+    /// Encode `cmd` into `buf`
+    fn encode<T: TryFrom<u32>>(cmd: &CMD, buf: &mut PulseBuffer<T>);
+
+    /// The carrier `cmd` should be modulated with
+    ///
+    /// Defaults to the common 38 kHz carrier with a 33% duty cycle, like `Sender::carrier`
+    fn carrier(_cmd: &CMD) -> Carrier {
+        Carrier::new(38_000, 33)
+    }
+
+    /// Fill `buf` with the mark and space durations of `cmd` in microseconds
+    ///
+    /// Returns the number of durations, or None if they didn't fit in `buf`
+    fn pulses<T: TryFrom<u32>>(cmd: &CMD, buf: &mut [T]) -> Option<usize> {
+        let mut pb = PulseBuffer::new(buf);
+        Self::encode(cmd, &mut pb);
+        pb.finish()
+    }
+
+    /// Fill `buf` with the mark and space durations of `cmd` in ticks of a timer running at
+    /// `tickrate` Hz
+    ///
+    /// Returns the number of durations, or None if they didn't fit in `buf`
+    fn pulses_ticks<T: TryFrom<u32>>(cmd: &CMD, buf: &mut [T], tickrate: u32) -> Option<usize> {
+        let mut pb = PulseBuffer::with_tickrate(buf, tickrate);
+        Self::encode(cmd, &mut pb);
+        pb.finish()
+    }
+}
+
+/// Buffer of alternating mark and space durations, always starting and ending with a mark
+pub struct PulseBuffer<'a, T> {
+    buf: &'a mut [T],
+    len: usize,
+    /// Ticks per second
+    tickrate: u32,
+    /// Duration of the current mark or space in microseconds
+    cur: u32,
+    /// Current is a mark
+    mark: bool,
+    /// A duration didn't fit
+    overflow: bool,
+}
+
+impl<'a, T: TryFrom<u32>> PulseBuffer<'a, T> {
+    /// Buffer with durations in microseconds
+    pub fn new(buf: &'a mut [T]) -> Self {
+        Self::with_tickrate(buf, 1_000_000)
+    }
+
+    /// Buffer with durations in ticks at `tickrate` Hz
+    pub fn with_tickrate(buf: &'a mut [T], tickrate: u32) -> Self {
+        Self {
+            buf,
+            len: 0,
+            tickrate,
+            cur: 0,
+            mark: true,
+            overflow: false,
+        }
+    }
+
+    /// Add a mark of `us` microseconds. Consecutive marks are merged.
+    pub fn mark(&mut self, us: u32) {
+        self.add(true, us);
+    }
+
+    /// Add a space of `us` microseconds. Consecutive spaces are merged, and a space before the
+    /// first mark is dropped.
+    pub fn space(&mut self, us: u32) {
+        if self.len == 0 && self.cur == 0 {
+            return;
+        }
+        self.add(false, us);
+    }
+
+    /// Number of durations, or None if they didn't fit. A trailing space is dropped.
+    pub fn finish(mut self) -> Option<usize> {
+        if self.mark && self.cur > 0 {
+            self.flush();
+        }
+
+        if self.overflow {
+            None
+        } else {
+            Some(self.len)
+        }
+    }
+
+    fn add(&mut self, mark: bool, us: u32) {
+        if mark != self.mark {
+            self.flush();
+            self.mark = mark;
+        }
+        self.cur += us;
+    }
+
+    fn flush(&mut self) {
+        let ticks = u64::from(self.cur) * u64::from(self.tickrate) / 1_000_000;
+        let value = u32::try_from(ticks).ok().and_then(|t| T::try_from(t).ok());
+
+        match (self.buf.get_mut(self.len), value) {
+            (Some(slot), Some(value)) => *slot = value,
+            _ => self.overflow = true,
+        }
+
+        self.len += 1;
+        self.cur = 0;
+    }
+}
+
+/// Steps through a frame encoded as mark and space durations, followed by a gap
+#[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+pub(crate) struct FrameStepper<const N: usize> {
+    pulses: [u16; N],
+    len: usize,
+    /// Index of the current duration, `len` while in the gap
+    idx: usize,
+    /// End of the current duration in microseconds from the start of the frame
+    end: u32,
+    /// Space after the last mark in microseconds
+    gap: u32,
+    /// Timestamp of the start of the frame, set by the first step
+    start: Option<u32>,
+    samplerate: u32,
+    active: bool,
+    error: bool,
+}
+
+#[cfg(any(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+impl<const N: usize> FrameStepper<N> {
+    pub(crate) const fn new(samplerate: u32) -> Self {
+        Self {
+            pulses: [0; N],
+            len: 0,
+            idx: 0,
+            end: 0,
+            gap: 0,
+            start: None,
+            samplerate,
+            active: false,
+            error: false,
+        }
+    }
+
+    /// Load a frame. The frame and the gap after it take at least `period` microseconds.
+    pub(crate) fn load<F>(&mut self, encode: F, period: u32)
+    where
+        F: FnOnce(&mut PulseBuffer<u16>),
+    {
+        self.reset();
+
+        let mut buf = PulseBuffer::new(&mut self.pulses);
+        encode(&mut buf);
+
+        match buf.finish() {
+            Some(len) if len > 0 => {
+                let total: u32 = self.pulses[..len].iter().map(|&p| u32::from(p)).sum();
+                self.len = len;
+                self.end = self.pulses[0].into();
+                self.gap = period.saturating_sub(total);
+                self.active = true;
+            }
+            Some(_) => {}
+            None => self.error = true,
+        }
+    }
+
+    /// Is a frame, or the gap after it, being sent
+    pub(crate) fn busy(&self) -> bool {
+        self.active
+    }
+
+    pub(crate) fn step(&mut self, ts: u32) -> State {
+        if self.error {
+            return State::Error;
+        }
+        if !self.active {
+            return State::Idle;
+        }
+
+        let start = *self.start.get_or_insert(ts);
+        let elapsed = u64::from(ts.wrapping_sub(start)) * 1_000_000 / u64::from(self.samplerate);
+
+        while elapsed >= u64::from(self.end) {
+            if self.idx == self.len {
+                self.active = false;
+                return State::Idle;
+            }
+
+            self.idx += 1;
+            self.end += match self.pulses[..self.len].get(self.idx) {
+                Some(&p) => p.into(),
+                None => self.gap,
+            };
+        }
+
+        // Even indices are marks
+        State::Transmit(self.idx < self.len && self.idx & 0x1 == 0)
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.len = 0;
+        self.idx = 0;
+        self.end = 0;
+        self.gap = 0;
+        self.start = None;
+        self.active = false;
+        self.error = false;
+    }
 }
 
 #[cfg(feature = "embedded-hal")]
 /// Embedded hal IR Sender
 pub trait PwmPinSender<CMD>: Sender<CMD> {
+    /// Set the duty cycle of `pwm` to the one recommended for the carrier
+    ///
+    /// The pwm timer should run at the carrier frequency, `self.carrier().frequency`.
+    fn setup_pwm<PWMPIN, DUTY>(&self, pwm: &mut PWMPIN)
+    where
+        PWMPIN: embedded_hal::PwmPin<Duty = DUTY>,
+        DUTY: Into<u32> + TryFrom<u32>,
+    {
+        let duty = self.carrier().duty(pwm.get_max_duty().into());
+        if let Ok(duty) = DUTY::try_from(duty) {
+            pwm.set_duty(duty);
+        }
+    }
+
     /// Step the transmit loop and output on `pwm`
     fn step_pwm<PWMPIN, DUTY>(&mut self, ts: u32, pwm: &mut PWMPIN) -> State
     where
@@ -38,3 +289,109 @@ pub trait PwmPinSender<CMD>: Sender<CMD> {
         state
     }
 }
+
+#[cfg(feature = "embedded-hal")]
+/// Sender that modulates the carrier in software, for output on a plain `OutputPin`
+///
+/// The wrapped sender gives the envelope and must run at the same tickrate as the
+/// `CarrierSender`, which should be a multiple of the carrier frequency, for example
+/// 76 kHz or 114 kHz for a 38 kHz carrier. It must be stepped once for every tick.
+pub struct CarrierSender<S> {
+    sender: S,
+    /// Carrier period in ticks
+    period: u32,
+    /// Ticks of each period the output is on
+    on: u32,
+    /// Ticks into the current carrier period, restarted at the start of each mark
+    phase: u32,
+    /// The envelope was on in the last step
+    mark: bool,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<S> CarrierSender<S> {
+    /// Wrap `sender`, running at `tickrate`, with the recommended duty cycle of its carrier
+    pub fn new<CMD>(sender: S, tickrate: u32) -> Self
+    where
+        S: Sender<CMD>,
+    {
+        let duty_cycle = sender.carrier().duty_cycle;
+        Self::with_duty_cycle(sender, tickrate, duty_cycle)
+    }
+
+    /// Wrap `sender`, running at `tickrate`, with a duty cycle of `duty_cycle` percent
+    pub fn with_duty_cycle<CMD>(sender: S, tickrate: u32, duty_cycle: u8) -> Self
+    where
+        S: Sender<CMD>,
+    {
+        let frequency = sender.carrier().frequency;
+        let period = ((tickrate + frequency / 2) / frequency).max(1);
+        let on = ((period * u32::from(duty_cycle) + 50) / 100).clamp(1, period);
+
+        Self {
+            sender,
+            period,
+            on,
+            phase: 0,
+            mark: false,
+        }
+    }
+
+    /// Hand back the wrapped sender
+    pub fn into_inner(self) -> S {
+        self.sender
+    }
+
+    /// Step the transmit loop and output on `pin`
+    pub fn step_pin<CMD, PIN, PINERR>(&mut self, ts: u32, pin: &mut PIN) -> Result<State, PINERR>
+    where
+        S: Sender<CMD>,
+        PIN: embedded_hal::digital::v2::OutputPin<Error = PINERR>,
+    {
+        let state = self.step(ts);
+        match state {
+            State::Transmit(true) => pin.set_high()?,
+            _ => pin.set_low()?,
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<CMD, S: Sender<CMD>> Sender<CMD> for CarrierSender<S> {
+    fn load(&mut self, cmd: CMD) {
+        self.sender.load(cmd);
+    }
+
+    /// Step the transfer loop. A mark is returned as the modulated carrier.
+    fn step(&mut self, ts: u32) -> State {
+        match self.sender.step(ts) {
+            State::Transmit(true) => {
+                if !self.mark {
+                    self.mark = true;
+                    self.phase = 0;
+                }
+
+                let on = self.phase < self.on;
+                self.phase += 1;
+                if self.phase == self.period {
+                    self.phase = 0;
+                }
+                State::Transmit(on)
+            }
+            state => {
+                self.mark = false;
+                state
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.sender.reset();
+        self.mark = false;
+    }
+
+    fn carrier(&self) -> Carrier {
+        self.sender.carrier()
+    }
+}
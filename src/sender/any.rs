@@ -0,0 +1,316 @@
+//! Sender for commands of any of the enabled protocols
+
+use core::convert::TryFrom;
+
+#[cfg(feature = "nec")]
+use crate::protocols::nec::{Nec16, NecCommand, NecSamsung, NecStandard, NecTypeSender};
+#[cfg(feature = "rc5")]
+use crate::protocols::rc5::{Rc5Command, Rc5Sender};
+#[cfg(feature = "rc6")]
+use crate::protocols::rc6::{Rc6Cmd, Rc6Sender};
+#[cfg(feature = "sbp")]
+use crate::protocols::sbp::{SbpCommand, SbpSender};
+use crate::{
+    sender::{FrameStepper, PulseBuffer, PulseSender, Sender, State},
+    Carrier, Command, Protocol,
+};
+
+/// Max number of mark and space durations in a frame of any protocol
+const PULSES: usize = 80;
+
+#[derive(Debug, Copy, Clone)]
+/// Command for any of the enabled protocols
+pub enum AnyCommand {
+    #[cfg(feature = "nec")]
+    Nec(NecCommand<NecStandard>),
+    #[cfg(feature = "nec")]
+    Nec16(NecCommand<Nec16>),
+    #[cfg(feature = "nec")]
+    NecSamsung(NecCommand<NecSamsung>),
+    #[cfg(feature = "rc5")]
+    Rc5(Rc5Command),
+    #[cfg(feature = "rc6")]
+    Rc6(Rc6Cmd),
+    #[cfg(feature = "sbp")]
+    Sbp(SbpCommand),
+}
+
+impl AnyCommand {
+    /// Encode the command with the sender of its protocol
+    pub fn encode<T: TryFrom<u32>>(&self, buf: &mut PulseBuffer<T>) {
+        match self {
+            #[cfg(feature = "nec")]
+            AnyCommand::Nec(cmd) => NecTypeSender::encode(cmd, buf),
+            #[cfg(feature = "nec")]
+            AnyCommand::Nec16(cmd) => NecTypeSender::encode(cmd, buf),
+            #[cfg(feature = "nec")]
+            AnyCommand::NecSamsung(cmd) => NecTypeSender::encode(cmd, buf),
+            #[cfg(feature = "rc5")]
+            AnyCommand::Rc5(cmd) => Rc5Sender::encode(cmd, buf),
+            #[cfg(feature = "rc6")]
+            AnyCommand::Rc6(cmd) => Rc6Sender::encode(cmd, buf),
+            #[cfg(feature = "sbp")]
+            AnyCommand::Sbp(cmd) => SbpSender::encode(cmd, buf),
+        }
+    }
+
+    fn command(&self) -> &dyn Command {
+        match self {
+            #[cfg(feature = "nec")]
+            AnyCommand::Nec(cmd) => cmd,
+            #[cfg(feature = "nec")]
+            AnyCommand::Nec16(cmd) => cmd,
+            #[cfg(feature = "nec")]
+            AnyCommand::NecSamsung(cmd) => cmd,
+            #[cfg(feature = "rc5")]
+            AnyCommand::Rc5(cmd) => cmd,
+            #[cfg(feature = "rc6")]
+            AnyCommand::Rc6(cmd) => cmd,
+            #[cfg(feature = "sbp")]
+            AnyCommand::Sbp(cmd) => cmd,
+        }
+    }
+}
+
+impl Command for AnyCommand {
+    /// The protocol can't be chosen from the address and data alone, always None
+    fn construct(_addr: u32, _data: u32) -> Option<Self> {
+        None
+    }
+
+    fn address(&self) -> u32 {
+        self.command().address()
+    }
+
+    fn data(&self) -> u32 {
+        self.command().data()
+    }
+
+    fn protocol(&self) -> Protocol {
+        self.command().protocol()
+    }
+
+    fn is_repeat(&self) -> bool {
+        self.command().is_repeat()
+    }
+
+    fn toggle(&self) -> Option<bool> {
+        self.command().toggle()
+    }
+
+    #[cfg_attr(not(any(feature = "rc5", feature = "rc6")), allow(unused_variables))]
+    fn set_toggle(&mut self, toggle: bool) {
+        #[allow(unreachable_patterns)]
+        match self {
+            #[cfg(feature = "rc5")]
+            AnyCommand::Rc5(cmd) => cmd.set_toggle(toggle),
+            #[cfg(feature = "rc6")]
+            AnyCommand::Rc6(cmd) => cmd.set_toggle(toggle),
+            _ => {}
+        }
+    }
+}
+
+macro_rules! from_cmd {
+    ($($feature:literal, $variant:ident, $cmd:ty;)*) => {
+        $(
+            #[cfg(feature = $feature)]
+            impl From<$cmd> for AnyCommand {
+                fn from(cmd: $cmd) -> Self {
+                    AnyCommand::$variant(cmd)
+                }
+            }
+        )*
+    };
+}
+
+from_cmd! {
+    "nec", Nec, NecCommand<NecStandard>;
+    "nec", Nec16, NecCommand<Nec16>;
+    "nec", NecSamsung, NecCommand<NecSamsung>;
+    "rc5", Rc5, Rc5Command;
+    "rc6", Rc6, Rc6Cmd;
+    "sbp", Sbp, SbpCommand;
+}
+
+/// Sender for commands of any of the enabled protocols
+///
+/// Each frame is followed by a gap that pads it to the frame period of its protocol. A command
+/// loaded while a frame or its gap is being sent, is sent after it, so commands of different
+/// protocols can be sent back to back. Only one command can wait: `Sender::load` replaces the
+/// waiting command, while `try_load` hands the new one back.
+///
+/// The carrier follows the protocol of the frame being sent.
+pub struct AnySender {
+    frame: FrameStepper<PULSES>,
+    /// Protocol of the frame being sent
+    protocol: Protocol,
+    /// Command waiting for the current frame to finish
+    next: Option<AnyCommand>,
+}
+
+impl AnySender {
+    pub fn new(samplerate: u32) -> Self {
+        Self {
+            frame: FrameStepper::new(samplerate),
+            protocol: Protocol::Unknown,
+            next: None,
+        }
+    }
+
+    /// Load `cmd`, unless another command is already waiting. Then `cmd` is returned.
+    pub fn try_load(&mut self, cmd: AnyCommand) -> Result<(), AnyCommand> {
+        if self.next.is_some() {
+            return Err(cmd);
+        }
+        self.load(cmd);
+        Ok(())
+    }
+
+    fn start(&mut self, cmd: AnyCommand) {
+        self.protocol = cmd.protocol();
+        let period = self.protocol.frame_period().unwrap_or(0);
+        self.frame.load(|buf| cmd.encode(buf), period);
+    }
+}
+
+impl Sender<AnyCommand> for AnySender {
+    /// Load `cmd`. While a frame is sent, it replaces any command already waiting.
+    fn load(&mut self, cmd: AnyCommand) {
+        if self.frame.busy() {
+            self.next = Some(cmd);
+        } else {
+            self.start(cmd);
+        }
+    }
+
+    fn step(&mut self, ts: u32) -> State {
+        match self.frame.step(ts) {
+            State::Idle => match self.next.take() {
+                Some(cmd) => {
+                    self.start(cmd);
+                    self.frame.step(ts)
+                }
+                None => State::Idle,
+            },
+            state => state,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.frame.reset();
+        self.next = None;
+    }
+
+    fn carrier(&self) -> Carrier {
+        self.protocol
+            .carrier()
+            .unwrap_or(Protocol::Nec.carrier().unwrap())
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl crate::sender::PwmPinSender<AnyCommand> for AnySender {}
+
+#[cfg(test)]
+#[cfg(all(feature = "nec", feature = "rc5", feature = "rc6", feature = "sbp"))]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{AnyCommand, AnySender};
+    use crate::protocols::{
+        nec::NecCommand, rc5::Rc5Command, rc6::Rc6Cmd, sbp::SbpCommand, Nec, Rc5, Rc6, Sbp,
+    };
+    use crate::recv::{EventReceiver, ReceiverSM};
+    use crate::sender::{Sender, State};
+    use crate::Command;
+
+    fn receive<SM: ReceiverSM>(frame: &[(bool, u32)]) -> SM::Cmd {
+        let mut recv: EventReceiver<SM> = EventReceiver::new(1_000_000);
+        frame
+            .iter()
+            .find_map(|&(edge, dt)| recv.edge_event(edge, dt).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn back_to_back() {
+        let cmds: [AnyCommand; 4] = [
+            AnyCommand::Nec(NecCommand::new(7, 44)),
+            Rc5Command::new(20, 53, false).into(),
+            Rc6Cmd {
+                toggle: true,
+                ..Rc6Cmd::new(70, 2)
+            }
+            .into(),
+            SbpCommand::construct(32, 15).unwrap().into(),
+        ];
+
+        let mut sender = AnySender::new(1_000_000);
+        let mut queued = cmds.iter();
+        sender.load(*queued.next().unwrap());
+
+        // Start timestamp and edges of each frame
+        let mut frames: Vec<(u32, Vec<(bool, u32)>)> = Vec::new();
+        let mut level = false;
+        let mut last = 0;
+
+        for ts in 0..500_000 {
+            // Queue the next command as soon as the previous one has started
+            if sender.next.is_none() {
+                if let Some(cmd) = queued.next() {
+                    sender.load(*cmd);
+                }
+            }
+
+            let on = match sender.step(ts) {
+                State::Transmit(on) => on,
+                _ => false,
+            };
+
+            if on != level {
+                if on && (frames.is_empty() || ts - last > 20_000) {
+                    frames.push((ts, Vec::new()));
+                }
+                frames.last_mut().unwrap().1.push((on, ts - last));
+                level = on;
+                last = ts;
+            }
+        }
+
+        assert_eq!(frames.len(), 4);
+
+        // Each frame starts one frame period after the previous
+        let starts: Vec<u32> = frames.iter().map(|f| f.0).collect();
+        assert_eq!(starts, [0, 108_000, 221_778, 328_445]);
+
+        let nec = receive::<Nec>(&frames[0].1);
+        assert_eq!((nec.addr, nec.cmd), (7, 44));
+        let rc5 = receive::<Rc5>(&frames[1].1);
+        assert_eq!((rc5.addr, rc5.cmd), (20, 53));
+        let rc6 = receive::<Rc6>(&frames[2].1);
+        assert_eq!((rc6.addr, rc6.cmd, rc6.toggle), (70, 2, true));
+        let sbp = receive::<Sbp>(&frames[3].1);
+        assert_eq!((sbp.address, sbp.command, sbp.valid), (32, 15, true));
+    }
+
+    #[test]
+    fn one_waiting() {
+        let first = AnyCommand::Nec(NecCommand::new(7, 44));
+        let second: AnyCommand = Rc5Command::new(20, 53, false).into();
+        let third: AnyCommand = Rc5Command::new(20, 54, false).into();
+
+        let mut sender = AnySender::new(1_000_000);
+
+        // The first is sent right away and the second waits for it
+        assert!(sender.try_load(first).is_ok());
+        assert!(sender.try_load(second).is_ok());
+
+        let rejected = sender.try_load(third).unwrap_err();
+        assert_eq!(rejected.data(), 54);
+        assert_eq!(sender.next.map(|cmd| cmd.data()), Some(53));
+
+        sender.load(third);
+        assert_eq!(sender.next.map(|cmd| cmd.data()), Some(54));
+    }
+}
@@ -0,0 +1,350 @@
+//! Transmit queue
+
+use crate::{
+    sender::{Sender, State},
+    Carrier, Command,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Transmit queue entry
+pub enum Entry<CMD> {
+    /// Send the command, `times` frames in a row
+    Command { cmd: CMD, times: u8 },
+    /// Wait for the given number of milliseconds
+    Delay(u32),
+}
+
+/// Fixed capacity queue of commands and delays, sent with `S`
+///
+/// Repeated frames of a command are started one frame period of the protocol apart, and a new
+/// frame is never started before the frame period of the previous one has passed.
+///
+/// Each command entry is a separate press, the toggle bit is flipped for each of them and kept
+/// for its repeats. Entries sent zero times, and commands of protocols without a toggle bit,
+/// leave the toggle bit as it is.
+pub struct TransmitQueue<CMD, S, const N: usize> {
+    sender: S,
+    entries: [Option<Entry<CMD>>; N],
+    /// Index of the first entry
+    head: usize,
+    len: usize,
+    samplerate: u32,
+    /// Command being sent and the number of frames left after the current
+    current: Option<(CMD, u8)>,
+    /// A frame is being sent by the sender
+    sending: bool,
+    /// Timestamp of the start of the last frame
+    frame_start: u32,
+    /// Start and length, in samples, of a gap or delay
+    wait: Option<(u32, u32)>,
+    toggle: bool,
+}
+
+impl<CMD, S, const N: usize> TransmitQueue<CMD, S, N>
+where
+    CMD: Command + Copy,
+    S: Sender<CMD>,
+{
+    /// Create a new queue for `sender` running at `samplerate`
+    pub fn new(sender: S, samplerate: u32) -> Self {
+        Self {
+            sender,
+            entries: [None; N],
+            head: 0,
+            len: 0,
+            samplerate,
+            current: None,
+            sending: false,
+            frame_start: 0,
+            wait: None,
+            toggle: false,
+        }
+    }
+
+    /// Add an entry to the queue. Returns the entry back if the queue is full.
+    pub fn push(&mut self, entry: Entry<CMD>) -> Result<(), Entry<CMD>> {
+        if self.len == N {
+            return Err(entry);
+        }
+
+        self.entries[(self.head + self.len) % N] = Some(entry);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Queue `cmd` to be sent once
+    pub fn send(&mut self, cmd: CMD) -> Result<(), Entry<CMD>> {
+        self.send_repeated(cmd, 1)
+    }
+
+    /// Queue `cmd` to be sent `times` frames in a row
+    pub fn send_repeated(&mut self, cmd: CMD, times: u8) -> Result<(), Entry<CMD>> {
+        self.push(Entry::Command { cmd, times })
+    }
+
+    /// Queue a delay of `ms` milliseconds
+    pub fn delay(&mut self, ms: u32) -> Result<(), Entry<CMD>> {
+        self.push(Entry::Delay(ms))
+    }
+
+    /// Nothing queued or being sent
+    pub fn is_idle(&self) -> bool {
+        self.len == 0 && self.current.is_none() && self.wait.is_none() && !self.sending
+    }
+
+    /// Drop the queued entries and stop the transmission
+    pub fn clear(&mut self) {
+        self.entries = [None; N];
+        self.head = 0;
+        self.len = 0;
+        self.current = None;
+        self.sending = false;
+        self.wait = None;
+        self.sender.reset();
+    }
+
+    /// Hand back the sender
+    pub fn into_inner(self) -> S {
+        self.sender
+    }
+
+    /// Step the transmit loop
+    ///
+    /// Returns `Transmit(false)` during gaps and delays, and `Idle` when the queue is empty.
+    pub fn step(&mut self, ts: u32) -> State {
+        loop {
+            if let Some((start, len)) = self.wait {
+                if ts.wrapping_sub(start) < len {
+                    return State::Transmit(false);
+                }
+                self.wait = None;
+            }
+
+            if self.sending {
+                match self.sender.step(ts) {
+                    State::Idle => {
+                        self.sending = false;
+                        self.wait = self
+                            .current
+                            .map(|(cmd, _)| (self.frame_start, self.period(&cmd)));
+                        continue;
+                    }
+                    State::Error => {
+                        self.sending = false;
+                        self.current = None;
+                        return State::Error;
+                    }
+                    state => return state,
+                }
+            }
+
+            match self.current {
+                Some((cmd, left)) if left > 0 => {
+                    self.current = Some((cmd, left - 1));
+                    self.sender.load(cmd);
+                    self.sending = true;
+                    self.frame_start = ts;
+                    continue;
+                }
+                _ => self.current = None,
+            }
+
+            match self.pop() {
+                Some(Entry::Command { mut cmd, times }) => {
+                    if times > 0 && cmd.toggle().is_some() {
+                        self.toggle = !self.toggle;
+                        cmd.set_toggle(self.toggle);
+                    }
+                    self.current = Some((cmd, times));
+                }
+                Some(Entry::Delay(ms)) => {
+                    self.wait = Some((ts, self.samples(u64::from(ms) * 1000)));
+                }
+                None => return State::Idle,
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<Entry<CMD>> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let entry = self.entries[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        entry
+    }
+
+    /// Frame period of the protocol of `cmd` in samples
+    fn period(&self, cmd: &CMD) -> u32 {
+        self.samples(cmd.protocol().frame_period().unwrap_or(0).into())
+    }
+
+    fn samples(&self, us: u64) -> u32 {
+        (us * u64::from(self.samplerate) / 1_000_000) as u32
+    }
+}
+
+impl<CMD, S, const N: usize> Sender<CMD> for TransmitQueue<CMD, S, N>
+where
+    CMD: Command + Copy,
+    S: Sender<CMD>,
+{
+    /// Queue `cmd` to be sent once. The command is dropped if the queue is full.
+    fn load(&mut self, cmd: CMD) {
+        let _ = self.send(cmd);
+    }
+
+    fn step(&mut self, ts: u32) -> State {
+        TransmitQueue::step(self, ts)
+    }
+
+    fn reset(&mut self) {
+        self.clear();
+    }
+
+    fn carrier(&self) -> Carrier {
+        self.sender.carrier()
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<CMD, S, const N: usize> crate::sender::PwmPinSender<CMD> for TransmitQueue<CMD, S, N>
+where
+    CMD: Command + Copy,
+    S: Sender<CMD>,
+{
+}
+
+#[cfg(test)]
+#[cfg(feature = "rc5")]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{Entry, TransmitQueue};
+    use crate::protocols::rc5::{Rc5, Rc5Command, Rc5Sender};
+    use crate::recv::EventReceiver;
+    use crate::sender::{Sender, State};
+
+    #[test]
+    fn repeats_and_delays() {
+        let samplerate = 100_000;
+        let mut queue: TransmitQueue<_, _, 4> =
+            TransmitQueue::new(Rc5Sender::new(samplerate), samplerate);
+
+        let play = Rc5Command::new(20, 53, false);
+        let stop = Rc5Command::new(20, 54, false);
+
+        queue.send_repeated(play, 3).unwrap();
+        queue.delay(2000).unwrap();
+        queue.send(stop).unwrap();
+        queue.send(play).unwrap();
+        assert_eq!(
+            queue.send(stop),
+            Err(Entry::Command {
+                cmd: stop,
+                times: 1
+            })
+        );
+
+        let mut recv: EventReceiver<Rc5> = EventReceiver::new(samplerate);
+        let mut received = Vec::new();
+        let mut level = false;
+        let mut last = 0;
+
+        let mut ts = 0;
+        while !queue.is_idle() {
+            let on = matches!(queue.step(ts), State::Transmit(true));
+            if on != level {
+                if let Ok(Some(cmd)) = recv.edge_event(on, ts - last) {
+                    received.push((ts, cmd.cmd, cmd.toggle));
+                }
+                level = on;
+                last = ts;
+            }
+            ts += 1;
+        }
+
+        let cmds: Vec<_> = received.iter().map(|r| (r.1, r.2)).collect();
+        assert_eq!(cmds, [(53, 1), (53, 1), (53, 1), (54, 0), (53, 1)]);
+
+        // Repeats are one frame period apart, the delay starts after the period of the last
+        let starts: Vec<_> = received.windows(2).map(|w| w[1].0 - w[0].0).collect();
+        assert_eq!(starts[0], 11_377);
+        assert_eq!(starts[1], 11_377);
+        assert_eq!(starts[2], 11_377 + 200_000);
+        assert_eq!(starts[3], 11_377);
+    }
+
+    /// Sender recording the loaded commands, each frame is sent in one step
+    struct Recorder<CMD>(Vec<CMD>);
+
+    impl<CMD> Sender<CMD> for Recorder<CMD> {
+        fn load(&mut self, cmd: CMD) {
+            self.0.push(cmd);
+        }
+
+        fn step(&mut self, _ts: u32) -> State {
+            State::Idle
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn toggle_only_sent_frames() {
+        let mut queue: TransmitQueue<_, _, 4> = TransmitQueue::new(Recorder(Vec::new()), 1000);
+
+        queue.send(Rc5Command::new(20, 53, false)).unwrap();
+        queue
+            .send_repeated(Rc5Command::new(20, 54, false), 0)
+            .unwrap();
+        queue.send(Rc5Command::new(20, 55, false)).unwrap();
+
+        let mut ts = 0;
+        while !queue.is_idle() {
+            queue.step(ts);
+            ts += 1;
+        }
+
+        let toggles: Vec<_> = queue
+            .into_inner()
+            .0
+            .iter()
+            .map(|cmd| (cmd.cmd, cmd.toggle))
+            .collect();
+        assert_eq!(toggles, [(53, 1), (55, 0)]);
+    }
+
+    #[test]
+    #[cfg(feature = "nec")]
+    fn toggle_skips_protocols_without_toggle() {
+        use crate::protocols::nec::{NecCommand, NecStandard};
+        use crate::sender::AnyCommand;
+        use crate::Command;
+
+        let mut queue: TransmitQueue<AnyCommand, _, 4> =
+            TransmitQueue::new(Recorder(Vec::new()), 1000);
+
+        queue.send(Rc5Command::new(20, 53, false).into()).unwrap();
+        queue
+            .send(NecCommand::<NecStandard>::new(7, 2).into())
+            .unwrap();
+        queue.send(Rc5Command::new(20, 53, false).into()).unwrap();
+
+        let mut ts = 0;
+        while !queue.is_idle() {
+            queue.step(ts);
+            ts += 1;
+        }
+
+        let toggles: Vec<_> = queue
+            .into_inner()
+            .0
+            .iter()
+            .map(|cmd| cmd.toggle())
+            .collect();
+        assert_eq!(toggles, [Some(true), None, Some(false)]);
+    }
+}
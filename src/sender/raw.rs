@@ -0,0 +1,158 @@
+//! Sender for raw timing sequences
+
+use crate::{
+    sender::{Sender, State},
+    Carrier,
+};
+
+/// Sender that replays a sequence of mark and space durations, for example a frame recorded
+/// with `RawCapture`
+///
+/// The durations are in micro seconds, starting with a mark. Each frame is followed by the gap,
+/// and the frame is sent the repeat count number of times.
+pub struct RawSender<'a> {
+    pulses: &'a [u32],
+    carrier: Carrier,
+    samplerate: u32,
+    /// Number of frames to send
+    repeats: u32,
+    /// Space after each frame, in micro seconds
+    gap: u32,
+    /// Frames sent
+    sent: u32,
+    /// Index of the current duration, `pulses.len()` during the gap
+    idx: usize,
+    /// End of the current duration, in micro seconds from the start
+    end: u64,
+    /// Timestamp of the start, set by the first step
+    start: Option<u32>,
+    active: bool,
+}
+
+impl<'a> RawSender<'a> {
+    /// Create a new sender running at `samplerate`, sending each frame once without a gap
+    pub fn new(samplerate: u32, carrier: Carrier) -> Self {
+        Self::with_repeats(samplerate, carrier, 1, 0)
+    }
+
+    /// Create a new sender running at `samplerate`
+    /// `repeats`: Number of times to send each frame
+    /// `gap_us`: Space after each frame, in micro seconds
+    pub fn with_repeats(samplerate: u32, carrier: Carrier, repeats: u32, gap_us: u32) -> Self {
+        Self {
+            pulses: &[],
+            carrier,
+            samplerate,
+            repeats,
+            gap: gap_us,
+            sent: 0,
+            idx: 0,
+            end: 0,
+            start: None,
+            active: false,
+        }
+    }
+}
+
+impl<'a> Sender<&'a [u32]> for RawSender<'a> {
+    fn load(&mut self, pulses: &'a [u32]) {
+        self.pulses = pulses;
+        self.sent = 0;
+        self.idx = 0;
+        self.end = pulses.first().copied().unwrap_or(0).into();
+        self.start = None;
+        self.active = !pulses.is_empty() && self.repeats > 0;
+    }
+
+    fn step(&mut self, ts: u32) -> State {
+        if !self.active {
+            return State::Idle;
+        }
+
+        let start = *self.start.get_or_insert(ts);
+        let elapsed = u64::from(ts.wrapping_sub(start)) * 1_000_000 / u64::from(self.samplerate);
+        let len = self.pulses.len();
+
+        while elapsed >= self.end {
+            self.idx += 1;
+
+            if self.idx < len {
+                self.end += u64::from(self.pulses[self.idx]);
+            } else if self.idx == len {
+                self.end += u64::from(self.gap);
+            } else {
+                self.sent += 1;
+                if self.sent == self.repeats {
+                    self.active = false;
+                    return State::Idle;
+                }
+                self.idx = 0;
+                self.end += u64::from(self.pulses[0]);
+            }
+        }
+
+        // Even indices are marks
+        State::Transmit(self.idx < len && self.idx & 0x1 == 0)
+    }
+
+    fn reset(&mut self) {
+        self.active = false;
+        self.start = None;
+    }
+
+    fn carrier(&self) -> Carrier {
+        self.carrier
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl<'a> crate::sender::PwmPinSender<&'a [u32]> for RawSender<'a> {}
+
+#[cfg(test)]
+#[cfg(feature = "nec")]
+mod tests {
+    use std::vec::Vec;
+
+    use super::RawSender;
+    use crate::protocols::{nec, Nec};
+    use crate::recv::EventReceiver;
+    use crate::sender::{Sender, State};
+    use crate::Carrier;
+
+    #[test]
+    fn replay() {
+        let pulses = nec::tests::frame();
+
+        let samplerate = 20_000;
+        let mut sender = RawSender::with_repeats(samplerate, Carrier::new(38_000, 33), 3, 40_000);
+        sender.load(&pulses);
+
+        let mut recv: EventReceiver<Nec> = EventReceiver::new(samplerate);
+        let mut received = Vec::new();
+        let (mut level, mut last) = (false, 0);
+        let mut ts = 0;
+
+        while let State::Transmit(on) = sender.step(ts) {
+            if on != level {
+                if let Ok(Some(cmd)) = recv.edge_event(on, ts - last) {
+                    received.push((ts, cmd));
+                }
+                level = on;
+                last = ts;
+            }
+            ts += 1;
+        }
+
+        assert_eq!(received.len(), 3);
+        assert!(received
+            .iter()
+            .all(|(_, cmd)| (cmd.addr, cmd.cmd) == (7, 44)));
+
+        // Frame and the gap, in samples
+        let frame_us: u32 = pulses.iter().sum::<u32>() + 40_000;
+        let spacing = received[1].0 - received[0].0;
+        assert!((spacing as i32 - (frame_us / 50) as i32).abs() <= 1);
+        // Done after the trailing gap
+        assert_eq!(ts, (3 * frame_us + 49) / 50);
+    }
+}
@@ -0,0 +1,91 @@
+//! Receiver statistics
+//!
+//! With the `stats` feature the receivers count the frames they decode and the ones they
+//! drop, for health metrics of devices in the field.
+
+use crate::recv::Error;
+use crate::Command;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+/// Receiver statistics counters. All counters wrap around.
+pub struct Statistics {
+    /// Frames decoded, repeats included
+    pub frames: u32,
+    /// Repeat frames decoded
+    pub repeats: u32,
+    /// Frames dropped with `Error::Address`
+    pub address_errors: u32,
+    /// Frames dropped with `Error::Data`
+    pub data_errors: u32,
+    /// Frames dropped with `Error::Header`
+    pub header_errors: u32,
+    /// Frames dropped with `Error::PulseWidth`
+    pub pulse_width_errors: u32,
+    /// Frames dropped with `Error::Checksum`
+    pub checksum_errors: u32,
+    /// Frames dropped with `Error::UnexpectedEdge`
+    pub edge_errors: u32,
+    /// Frames dropped with `Error::Other`
+    pub other_errors: u32,
+    /// Frames abandoned by the idle timeout or dropped with `Error::Timeout`
+    pub timeouts: u32,
+    /// Glitches dropped by a `GlitchFilter`
+    pub glitches: u32,
+}
+
+impl Statistics {
+    /// Total number of errors, timeouts not included
+    pub fn errors(&self) -> u32 {
+        [
+            self.address_errors,
+            self.data_errors,
+            self.header_errors,
+            self.pulse_width_errors,
+            self.checksum_errors,
+            self.edge_errors,
+            self.other_errors,
+        ]
+        .iter()
+        .fold(0, |sum, n| sum.wrapping_add(*n))
+    }
+
+    /// Count the result of an edge event
+    pub(crate) fn result<C: Command>(&mut self, res: &Result<Option<C>, Error>) {
+        match res {
+            Ok(Some(cmd)) => self.command(cmd),
+            Ok(None) => {}
+            Err(err) => {
+                let counter = match err {
+                    Error::Address => &mut self.address_errors,
+                    Error::Data => &mut self.data_errors,
+                    Error::Header => &mut self.header_errors,
+                    Error::PulseWidth { .. } => &mut self.pulse_width_errors,
+                    Error::Checksum => &mut self.checksum_errors,
+                    Error::UnexpectedEdge { .. } => &mut self.edge_errors,
+                    Error::Timeout { .. } => &mut self.timeouts,
+                    Error::Other => &mut self.other_errors,
+                };
+                *counter = counter.wrapping_add(1);
+            }
+        }
+    }
+
+    /// Count a timed out frame, `cmd` if the timeout completed it
+    pub(crate) fn timeout<C: Command>(&mut self, cmd: Option<&C>) {
+        match cmd {
+            Some(cmd) => self.command(cmd),
+            None => self.timeouts = self.timeouts.wrapping_add(1),
+        }
+    }
+
+    pub(crate) fn glitches(&mut self, glitches: u32) {
+        self.glitches = self.glitches.wrapping_add(glitches);
+    }
+
+    fn command<C: Command>(&mut self, cmd: &C) {
+        self.frames = self.frames.wrapping_add(1);
+        if cmd.is_repeat() {
+            self.repeats = self.repeats.wrapping_add(1);
+        }
+    }
+}